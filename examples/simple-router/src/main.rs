@@ -10,16 +10,6 @@ fn main() {
 fn app(cx: Scope) -> Element {
   cx.render(rsx! {
     BrowserRouter {
-      Routes {
-        Route {
-          path: "/posts",
-          element: Promo,
-        }
-        Route {
-          path: "/posts/*p",
-          element: Promo,
-        }
-      }
       Routes {
         Layout {
           Route {
@@ -29,6 +19,10 @@ fn app(cx: Scope) -> Element {
           Route {
             path: "/posts",
             element: Blog,
+            Slot {
+              name: "promo",
+              element: Promo,
+            }
             Route {
               path: ":id"
               element: Post
@@ -39,6 +33,13 @@ fn app(cx: Scope) -> Element {
           }
         }
       }
+      Routes {
+        modal: true,
+        Route {
+          path: "/posts/:id",
+          element: PostModal,
+        }
+      }
     }
   })
 }
@@ -50,6 +51,7 @@ fn Layout<'a>(cx: Scope<'a>, children: Element<'a>) -> Element<'a> {
     NavLink { to: "/", "Home" }
     " | "
     NavLink { to: "/posts", "Posts" }
+    Outlet { name: "promo" }
     div {
       style: "padding: .75rem;",
       children
@@ -75,7 +77,15 @@ fn Blog(cx: Scope) -> Element {
           "Post #1"
         }
       }
+      li {
+        Link {
+          to: "/posts/2",
+          modal: true,
+          "Post #2 (modal)"
+        }
+      }
     }
+    Outlet {}
   })
 }
 
@@ -88,9 +98,14 @@ fn NotFound(cx: Scope) -> Element {
 
 #[allow(non_snake_case)]
 fn Post(cx: Scope) -> Element {
-  cx.render(rsx! {
-    div { "Post #1" }
-  })
+  match use_param::<u32>(&cx, "id") {
+    Ok(id) => cx.render(rsx! {
+      div { "Post #{id}" }
+    }),
+    Err(err) => cx.render(rsx! {
+      div { "{err}" }
+    }),
+  }
 }
 
 #[allow(non_snake_case)]
@@ -103,3 +118,22 @@ fn Promo(cx: Scope) -> Element {
     }
   })
 }
+
+#[allow(non_snake_case)]
+fn PostModal(cx: Scope) -> Element {
+  let router_core = use_context::<RouterCore>(&cx)?;
+
+  cx.render(rsx! {
+    div {
+      style: "position: fixed; inset: 0; display: flex; align-items: center; justify-content: center; background: rgba(0, 0, 0, .5);",
+      div {
+        style: "padding: 1rem; background: white;",
+        Post {}
+        button {
+          onclick: move |_| router_core.write().back(),
+          "Close"
+        }
+      }
+    }
+  })
+}
@@ -7,10 +7,11 @@ use proc_macro::{Span, TokenStream};
 use proc_macro_error::proc_macro_error;
 use quote::{quote, ToTokens};
 use serde::{Deserialize, Serialize};
-use syn::{parse_macro_input, AttributeArgs, Ident, ItemFn};
+use syn::{parse_macro_input, AttributeArgs, DeriveInput, Ident, ItemFn};
 
 mod api;
 mod page;
+mod routable;
 
 const TARGET_PATH: &'static str = "target/ahecha";
 
@@ -106,6 +107,12 @@ pub fn route(attr: TokenStream, item: TokenStream) -> TokenStream {
   quote!( #[cfg(not(target_arch = "wasm32"))] #item_fn ).into()
 }
 
+#[proc_macro_error]
+#[proc_macro_derive(Routable, attributes(at))]
+pub fn routable(item: TokenStream) -> TokenStream {
+  routable::derive(parse_macro_input!(item as DeriveInput)).into()
+}
+
 #[proc_macro_error]
 #[proc_macro]
 pub fn monkey_path_clean(item: TokenStream) -> TokenStream {
@@ -141,11 +141,18 @@ impl ToTokens for DynamicPageRoute {
 
         let res = #api_module_path ( #handler_ident_args ).await;
         #vdom_init
+        let server_context = vdom.base_scope().provide_context(::ahecha::ServerContext::default());
 
-        let _ = vdom.rebuild();
-        axum::response::Html(
-          index_html.replace(r#"<div id="main"></div>"#, &format!(r#"<div id="main">{}</div>"#, &dioxus::ssr::render_vdom(&vdom)))
-        )
+        server_context.render(&mut vdom);
+
+        if let Some(to) = server_context.redirect_to() {
+          return axum::response::IntoResponse::into_response(axum::response::Redirect::temporary(&to));
+        }
+
+        let html = index_html.replace(r#"<div id="main"></div>"#, &format!(r#"<div id="main">{}</div>"#, &dioxus::ssr::render_vdom(&vdom)));
+        axum::response::IntoResponse::into_response(axum::response::Html(
+          server_context.inject_head(&html)
+        ))
       }))
     )
     .to_tokens(tokens);
@@ -170,11 +177,18 @@ impl ToTokens for StaticPageRoute {
           ))
         }
         let mut vdom = VirtualDom::new(app);
+        let server_context = vdom.base_scope().provide_context(::ahecha::ServerContext::default());
 
-        let _ = vdom.rebuild();
-        axum::response::Html(
-          index_html.replace(r#"<div id="main"></div>"#, &format!(r#"<div id="main">{}</div>"#, &dioxus::ssr::render_vdom(&vdom)))
-        )
+        server_context.render(&mut vdom);
+
+        if let Some(to) = server_context.redirect_to() {
+          return axum::response::IntoResponse::into_response(axum::response::Redirect::temporary(&to));
+        }
+
+        let html = index_html.replace(r#"<div id="main"></div>"#, &format!(r#"<div id="main">{}</div>"#, &dioxus::ssr::render_vdom(&vdom)));
+        axum::response::IntoResponse::into_response(axum::response::Html(
+          server_context.inject_head(&html)
+        ))
       }))
     )
     .to_tokens(tokens);
@@ -0,0 +1,187 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::{abort, abort_call_site};
+use quote::quote;
+use syn::{
+  parse::{Parse, ParseStream},
+  spanned::Spanned,
+  DeriveInput, Fields, Ident, LitStr, Token,
+};
+
+struct RouteAttributes {
+  element: Option<syn::Path>,
+  path: LitStr,
+}
+
+impl Parse for RouteAttributes {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let path = input.parse()?;
+    let mut element = None;
+
+    while input.peek(Token![,]) {
+      input.parse::<Token![,]>()?;
+      if input.is_empty() {
+        break;
+      }
+
+      let key = input.parse::<Ident>()?;
+      input.parse::<Token![=]>()?;
+      if key == "element" {
+        element = Some(input.parse()?);
+      } else {
+        return Err(syn::Error::new(
+          key.span(),
+          "Only the `element` option is supported",
+        ));
+      }
+    }
+
+    Ok(Self { element, path })
+  }
+}
+
+pub(crate) fn derive(input: DeriveInput) -> TokenStream {
+  let ident = &input.ident;
+  let data = match &input.data {
+    syn::Data::Enum(data) => data,
+    _ => abort_call_site!("`Routable` can only be derived for enums"),
+  };
+
+  let mut routes = vec![];
+  let mut to_path_arms = vec![];
+  let mut from_route_arms = vec![];
+
+  for (index, variant) in data.variants.iter().enumerate() {
+    let variant_ident = &variant.ident;
+    let attributes = match variant.attrs.iter().find(|attr| attr.path.is_ident("at")) {
+      Some(attr) => match attr.parse_args::<RouteAttributes>() {
+        Ok(attributes) => attributes,
+        Err(err) => abort!(err.span(), "{}", err),
+      },
+      None => abort!(
+        variant.span(),
+        "The variant `{}` requires a `#[at(\"/path\")]` attribute",
+        variant_ident
+      ),
+    };
+    let path = attributes.path.value();
+
+    let (params, catch_alls): (Vec<_>, Vec<_>) = path
+      .split('/')
+      .filter_map(|segment| match segment.strip_prefix('*') {
+        Some(param) => Some((param.to_owned(), true)),
+        None => segment
+          .strip_prefix(':')
+          .map(|param| (param.to_owned(), false)),
+      })
+      .unzip();
+    let format = path
+      .split('/')
+      .map(|segment| {
+        if segment.starts_with(':') || segment.starts_with('*') {
+          "{}".to_owned()
+        } else {
+          segment.replace('{', "{{").replace('}', "}}")
+        }
+      })
+      .collect::<Vec<_>>()
+      .join("/");
+
+    match &variant.fields {
+      Fields::Unit => {
+        if !params.is_empty() {
+          abort!(
+            attributes.path.span(),
+            "The variant `{}` has no fields for the route params",
+            variant_ident
+          );
+        }
+
+        to_path_arms.push(quote!( Self::#variant_ident => #path.to_owned() ));
+        from_route_arms.push(quote!( #index => Some(Self::#variant_ident) ));
+      }
+      Fields::Named(fields) => {
+        let field_idents = fields
+          .named
+          .iter()
+          .filter_map(|field| field.ident.clone())
+          .collect::<Vec<_>>();
+
+        for field_ident in field_idents.iter() {
+          if !params.contains(&field_ident.to_string()) {
+            abort!(
+              field_ident.span(),
+              "The field `{}` is not a param of the route `{}`",
+              field_ident,
+              path
+            );
+          }
+        }
+
+        let param_idents = params
+          .iter()
+          .map(
+            |param| match field_idents.iter().find(|field| *field == param) {
+              Some(field_ident) => field_ident.clone(),
+              None => abort!(
+                attributes.path.span(),
+                "The route param `{}` has no matching field in `{}`",
+                param,
+                variant_ident
+              ),
+            },
+          )
+          .collect::<Vec<_>>();
+
+        to_path_arms.push(quote!(
+          Self::#variant_ident { #(#field_idents),* } => format!(
+            #format,
+            #(::ahecha::encode_route_param(&#param_idents.to_string(), #catch_alls)),*
+          )
+        ));
+        from_route_arms.push(quote!(
+          #index => Some(Self::#variant_ident {
+            #(#param_idents: params.get(#params)?.parse().ok()?),*
+          })
+        ));
+      }
+      Fields::Unnamed(_) => abort!(
+        variant.span(),
+        "Only unit and named fields variants are supported by `Routable`"
+      ),
+    }
+
+    let element = match attributes.element {
+      Some(element) => quote!( Some(#element as ::dioxus::prelude::Component) ),
+      None => quote!(None),
+    };
+    routes.push(quote!( (#path, #element) ));
+  }
+
+  quote!(
+    impl ::ahecha::Routable for #ident {
+      fn routes() -> Vec<(&'static str, Option<::dioxus::prelude::Component>)> {
+        vec![#(#routes),*]
+      }
+
+      fn to_path(&self) -> String {
+        match self {
+          #(#to_path_arms,)*
+        }
+      }
+
+      #[allow(unused_variables)]
+      fn from_route(index: usize, params: &::ahecha::RouteParams) -> Option<Self> {
+        match index {
+          #(#from_route_arms,)*
+          _ => None,
+        }
+      }
+    }
+
+    impl From<#ident> for String {
+      fn from(route: #ident) -> Self {
+        ::ahecha::Routable::to_path(&route)
+      }
+    }
+  )
+}
@@ -0,0 +1,101 @@
+use std::{
+  cell::{Cell, RefCell},
+  rc::Rc,
+};
+
+use dioxus::prelude::*;
+
+use crate::{Location, RouterCore};
+
+// Conditions of the mounted `use_blocker` hooks
+pub(crate) type Blockers = Rc<RefCell<Vec<Rc<Cell<bool>>>>>;
+
+/// Blocks every navigation through the router while `condition` holds: `push`, `replace`, `back`,
+/// `forward`, link clicks and the browser back/forward buttons. The blocked navigation is kept
+/// until the app calls `Blocker::proceed` or `Blocker::reset`. Closing or reloading the tab asks
+/// the browser to confirm meanwhile.
+pub fn use_blocker(cx: &ScopeState, condition: bool) -> Blocker<'_> {
+  let context = use_context::<RouterCore>(&cx)
+    .expect("`use_blocker` can be used only in components wrapped by a router");
+  let hook = cx.use_hook(|| BlockerHook::new(context.read().blockers.clone()));
+  hook.condition.set(condition);
+
+  #[cfg(target_arch = "wasm32")]
+  match (condition, hook.before_unload.is_some()) {
+    (true, false) => {
+      hook.before_unload = Some(gloo::events::EventListener::new_with_options(
+        &web_sys::window().unwrap(),
+        "beforeunload",
+        gloo::events::EventListenerOptions::enable_prevent_default(),
+        |event| {
+          use wasm_bindgen::JsCast;
+
+          event.prevent_default();
+          if let Some(event) = event.dyn_ref::<web_sys::BeforeUnloadEvent>() {
+            event.set_return_value("");
+          }
+        },
+      ));
+    }
+    (false, true) => hook.before_unload = None,
+    _ => {}
+  }
+
+  Blocker { context }
+}
+
+// Registers the condition of a `use_blocker` hook with the router, for as long as the hook lives
+struct BlockerHook {
+  blockers: Blockers,
+  condition: Rc<Cell<bool>>,
+  #[cfg(target_arch = "wasm32")]
+  before_unload: Option<gloo::events::EventListener>,
+}
+
+impl BlockerHook {
+  fn new(blockers: Blockers) -> Self {
+    let condition = Rc::new(Cell::new(false));
+    blockers.borrow_mut().push(condition.clone());
+
+    Self {
+      blockers,
+      condition,
+      #[cfg(target_arch = "wasm32")]
+      before_unload: None,
+    }
+  }
+}
+
+impl Drop for BlockerHook {
+  fn drop(&mut self) {
+    self
+      .blockers
+      .borrow_mut()
+      .retain(|condition| !Rc::ptr_eq(condition, &self.condition));
+  }
+}
+
+#[derive(Clone, Copy)]
+pub struct Blocker<'a> {
+  context: &'a UseSharedState<RouterCore>,
+}
+
+impl<'a> Blocker<'a> {
+  /// Whether a navigation is waiting for `proceed` or `reset`.
+  pub fn is_blocked(&self) -> bool {
+    self.context.read().blocked.is_some()
+  }
+
+  /// Where the waiting navigation goes to.
+  pub fn location(&self) -> Option<Location> {
+    self.context.read().blocked_location()
+  }
+
+  pub fn proceed(&self) {
+    self.context.write().proceed();
+  }
+
+  pub fn reset(&self) {
+    self.context.write().reset();
+  }
+}
@@ -0,0 +1,433 @@
+use std::{
+  cell::RefCell,
+  collections::HashSet,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use dioxus::prelude::*;
+
+use crate::ServerContext;
+
+impl ServerContext {
+  /// Adds the tags rendered by `Title` and `Meta` at the end of the `<head>` of `html`. The
+  /// `<title>` and the `<meta>` tags with the same `name` or `property` already in `html` are
+  /// replaced by the ones the page rendered.
+  pub fn inject_head(&self, html: &str) -> String {
+    let head = self.head.borrow();
+    let mut html = html.to_owned();
+
+    for tag in head.winners() {
+      match tag {
+        HeadTag::Meta {
+          attribute, name, ..
+        } => remove_meta_tags(&mut html, attribute, name),
+        HeadTag::Title(_) => {
+          if let (Some(start), Some(end)) = (html.find("<title>"), html.find("</title>")) {
+            html.replace_range(start..end + "</title>".len(), "");
+          }
+        }
+      }
+    }
+
+    let tags = head
+      .winners()
+      .iter()
+      .map(|tag| tag.to_html())
+      .collect::<String>();
+    html.replacen("</head>", &format!("{}</head>", tags), 1)
+  }
+}
+
+// Removes the `<meta>` tags with `attribute="name"` from the `<head>` of `html`.
+fn remove_meta_tags(html: &mut String, attribute: &str, name: &str) {
+  let quoted = [
+    format!("{}=\"{}\"", attribute, name),
+    format!("{}='{}'", attribute, name),
+  ];
+  let mut from = 0;
+
+  while let Some(start) = html[from..].find("<meta").map(|start| from + start) {
+    let end = match (html[start..].find('>'), html.find("</head>")) {
+      (Some(end), Some(head_end)) if start + end < head_end => start + end + 1,
+      _ => break,
+    };
+    let matches = html[start..end]
+      .split(char::is_whitespace)
+      .map(|attribute| attribute.trim_end_matches(&['/', '>'][..]))
+      .any(|attribute| quoted.iter().any(|quoted| quoted == attribute));
+
+    if matches {
+      html.replace_range(start..end, "");
+      from = start;
+    } else {
+      from = end;
+    }
+  }
+}
+
+// A tag `Title` or `Meta` renders into the document head
+#[derive(Clone, Debug, PartialEq)]
+enum HeadTag {
+  Meta {
+    attribute: &'static str,
+    content: String,
+    name: String,
+  },
+  Title(String),
+}
+
+impl HeadTag {
+  fn key(&self) -> String {
+    match self {
+      HeadTag::Meta {
+        attribute, name, ..
+      } => format!("meta[{}=\"{}\"]", attribute, name),
+      HeadTag::Title(_) => "title".to_owned(),
+    }
+  }
+
+  fn to_html(&self) -> String {
+    match self {
+      HeadTag::Meta {
+        attribute,
+        content,
+        name,
+      } => format!(
+        r#"<meta {}="{}" content="{}">"#,
+        attribute,
+        escape_html(name),
+        escape_html(content)
+      ),
+      HeadTag::Title(title) => format!("<title>{}</title>", escape_html(title)),
+    }
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn apply(&self) {
+    let document = match web_sys::window().and_then(|window| window.document()) {
+      Some(document) => document,
+      None => return,
+    };
+
+    match self {
+      HeadTag::Meta { content, .. } => {
+        let element = match document.query_selector(&self.key()) {
+          Ok(Some(element)) => Ok(element),
+          _ => self.create_meta(&document),
+        };
+        if let Err(err) = element.and_then(|element| element.set_attribute("content", content)) {
+          tracing::error!("{:?}", &err);
+        }
+      }
+      HeadTag::Title(title) => document.set_title(title),
+    }
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn create_meta(
+    &self,
+    document: &web_sys::Document,
+  ) -> Result<web_sys::Element, wasm_bindgen::JsValue> {
+    let element = document.create_element("meta")?;
+    if let HeadTag::Meta {
+      attribute, name, ..
+    } = self
+    {
+      element.set_attribute(attribute, name)?;
+    }
+    if let Some(head) = document.head() {
+      head.append_child(&element)?;
+    }
+    Ok(element)
+  }
+
+  // The document keeps its last title, there's nothing better to show.
+  #[cfg(target_arch = "wasm32")]
+  fn remove(&self) {
+    if let HeadTag::Meta { .. } = self {
+      if let Some(Ok(Some(element))) = web_sys::window()
+        .and_then(|window| window.document())
+        .map(|document| document.query_selector(&self.key()))
+      {
+        element.remove();
+      }
+    }
+  }
+}
+
+fn escape_html(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+// The head tags of the mounted components by hook id, with the height of their scope. For every
+// key the deepest one wins, so inner routes override outer ones, then the last one set.
+#[derive(Default)]
+pub(crate) struct HeadTags(Vec<(usize, u32, HeadTag)>);
+
+impl HeadTags {
+  fn set(&mut self, id: usize, height: u32, tag: HeadTag) {
+    self.0.retain(|(tag_id, _, _)| *tag_id != id);
+    self.0.push((id, height, tag));
+  }
+
+  fn remove(&mut self, id: usize) {
+    self.0.retain(|(tag_id, _, _)| *tag_id != id);
+  }
+
+  fn winner(&self, key: &str) -> Option<&HeadTag> {
+    self
+      .0
+      .iter()
+      .filter(|(_, _, tag)| tag.key() == key)
+      .max_by_key(|(_, height, _)| *height)
+      .map(|(_, _, tag)| tag)
+  }
+
+  fn winners(&self) -> Vec<&HeadTag> {
+    let keys = self
+      .0
+      .iter()
+      .map(|(_, _, tag)| tag.key())
+      .collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    keys
+      .into_iter()
+      .filter(|key| seen.insert(key.clone()))
+      .filter_map(|key| self.winner(&key))
+      .collect()
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+  static HEAD: RefCell<HeadTags> = RefCell::new(HeadTags::default());
+}
+
+static NEXT_HEAD_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Registers the tag of a `Title` or `Meta` while it's mounted, in the document on wasm and in the
+// `ServerContext` during server side rendering.
+fn use_head_tag(cx: &ScopeState, tag: HeadTag) {
+  let hook = cx.use_hook(|| HeadHook {
+    id: NEXT_HEAD_ID.fetch_add(1, Ordering::Relaxed),
+    server_context: cx.consume_context::<ServerContext>(),
+    tag: None,
+  });
+
+  if hook.tag.as_ref() != Some(&tag) {
+    hook.set(cx.height(), tag);
+  }
+}
+
+struct HeadHook {
+  id: usize,
+  server_context: Option<ServerContext>,
+  tag: Option<HeadTag>,
+}
+
+impl HeadHook {
+  fn set(&mut self, height: u32, tag: HeadTag) {
+    if let Some(server_context) = &self.server_context {
+      server_context
+        .head
+        .borrow_mut()
+        .set(self.id, height, tag.clone());
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    HEAD.with(|head| {
+      let mut head = head.borrow_mut();
+      head.set(self.id, height, tag.clone());
+      for tag in self.tag.iter().chain(Some(&tag)) {
+        match head.winner(&tag.key()) {
+          Some(winner) => winner.apply(),
+          None => tag.remove(),
+        }
+      }
+    });
+
+    self.tag = Some(tag);
+  }
+}
+
+impl Drop for HeadHook {
+  fn drop(&mut self) {
+    if let Some(server_context) = &self.server_context {
+      server_context.head.borrow_mut().remove(self.id);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(tag) = &self.tag {
+      HEAD.with(|head| {
+        let mut head = head.borrow_mut();
+        head.remove(self.id);
+        match head.winner(&tag.key()) {
+          Some(winner) => winner.apply(),
+          None => tag.remove(),
+        }
+      });
+    }
+  }
+}
+
+/// Groups the `Title` and `Meta` of a page. It renders nothing itself.
+#[allow(non_snake_case)]
+#[inline_props]
+pub fn Head<'a>(cx: Scope<'a>, children: Element<'a>) -> Element {
+  cx.render(rsx!(children))
+}
+
+/// Sets `document.title` while mounted, the title of the deepest mounted route wins.
+#[allow(non_snake_case)]
+#[inline_props]
+pub fn Title<'a>(cx: Scope<'a>, title: &'a str) -> Element {
+  use_head_tag(&cx, HeadTag::Title(title.to_string()));
+  None
+}
+
+#[derive(Props)]
+pub struct MetaProps<'a> {
+  content: &'a str,
+  name: Option<&'a str>,
+  property: Option<&'a str>,
+}
+
+/// Adds a `<meta name content>` (or `property` for Open Graph) to the document head while mounted.
+/// Tags with the same `name` or `property` are deduplicated, the deepest mounted route wins.
+#[allow(non_snake_case)]
+pub fn Meta<'a>(cx: Scope<'a, MetaProps<'a>>) -> Element {
+  let MetaProps {
+    content,
+    name,
+    property,
+  } = &cx.props;
+  let (attribute, name) = match (name, property) {
+    (Some(name), _) => ("name", name),
+    (None, Some(property)) => ("property", property),
+    (None, None) => {
+      tracing::error!("`Meta` requires a `name` or a `property`");
+      return None;
+    }
+  };
+
+  use_head_tag(
+    &cx,
+    HeadTag::Meta {
+      attribute,
+      content: content.to_string(),
+      name: name.to_string(),
+    },
+  );
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn deepest_head_tags_win() {
+    let mut head = HeadTags::default();
+    head.set(
+      0,
+      1,
+      HeadTag::Meta {
+        attribute: "name",
+        content: "outer".to_owned(),
+        name: "description".to_owned(),
+      },
+    );
+    head.set(1, 1, HeadTag::Title("Outer".to_owned()));
+    head.set(
+      2,
+      3,
+      HeadTag::Meta {
+        attribute: "name",
+        content: "inner".to_owned(),
+        name: "description".to_owned(),
+      },
+    );
+    head.set(3, 1, HeadTag::Title("Last".to_owned()));
+
+    assert_eq!(
+      head.winners(),
+      vec![
+        &HeadTag::Meta {
+          attribute: "name",
+          content: "inner".to_owned(),
+          name: "description".to_owned(),
+        },
+        &HeadTag::Title("Last".to_owned()),
+      ]
+    );
+
+    head.remove(2);
+    head.remove(3);
+    assert_eq!(
+      head.winners(),
+      vec![
+        &HeadTag::Meta {
+          attribute: "name",
+          content: "outer".to_owned(),
+          name: "description".to_owned(),
+        },
+        &HeadTag::Title("Outer".to_owned()),
+      ]
+    );
+  }
+
+  #[test]
+  fn inject_head_tags() {
+    let html = "<html><head><title>App</title></head><body></body></html>";
+    let server_context = ServerContext::default();
+    assert_eq!(server_context.inject_head(html), html);
+
+    server_context
+      .head
+      .borrow_mut()
+      .set(0, 1, HeadTag::Title("Posts & more".to_owned()));
+    assert_eq!(
+      server_context.inject_head(html),
+      "<html><head><title>Posts &amp; more</title></head><body></body></html>"
+    );
+  }
+
+  #[test]
+  fn inject_head_replaces_template_meta_tags() {
+    let html = concat!(
+      r#"<head><meta charset="utf-8"><meta name="description" content="App" />"#,
+      r#"<meta property='og:title' content="App"></head>"#
+    );
+    let server_context = ServerContext::default();
+    server_context.head.borrow_mut().set(
+      0,
+      1,
+      HeadTag::Meta {
+        attribute: "name",
+        content: "Posts".to_owned(),
+        name: "description".to_owned(),
+      },
+    );
+    server_context.head.borrow_mut().set(
+      1,
+      1,
+      HeadTag::Meta {
+        attribute: "property",
+        content: "Posts".to_owned(),
+        name: "og:title".to_owned(),
+      },
+    );
+
+    assert_eq!(
+      server_context.inject_head(html),
+      concat!(
+        r#"<head><meta charset="utf-8"><meta name="description" content="Posts">"#,
+        r#"<meta property="og:title" content="Posts"></head>"#
+      )
+    );
+  }
+}
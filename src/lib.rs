@@ -1,32 +1,284 @@
+use std::{
+  cell::{Cell, RefCell},
+  collections::{HashMap, HashSet},
+  future::Future,
+  marker::PhantomData,
+  pin::Pin,
+  rc::{Rc, Weak},
+  sync::Arc,
+};
+
 pub use ahecha_macros::*;
-use dioxus::prelude::*;
+use dioxus::{events::MouseEvent, prelude::*};
+use scroll::Scroll;
+#[cfg(target_arch = "wasm32")]
+use scroll::ScrollRestoration;
+use serde::{de::DeserializeOwned, Serialize};
+
+mod blocker;
+mod head;
+mod loader;
+mod scroll;
+
+pub use blocker::*;
+pub use head::*;
+pub use loader::*;
 
 pub trait RouterHistory {
   fn back(&mut self);
+  fn can_go_back(&self) -> bool;
+  fn can_go_forward(&self) -> bool;
   fn forward(&mut self);
   fn push(&mut self, url: impl AsRef<str>);
   fn replace(&mut self, url: impl AsRef<str>);
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Location {
+  pub pathname: String,
+  /// The query string, including the leading `?`, or empty.
+  pub search: String,
+  /// The fragment, including the leading `#`, or empty.
+  pub hash: String,
+  /// The JSON encoded state the entry was navigated to with, see `NavigateOptions::state`.
+  pub state: Option<String>,
+  /// The location kept rendered under this one, see `NavigateOptions::background`.
+  pub background: Option<Box<Location>>,
+}
+
+impl Location {
+  pub fn parse(url: &str) -> Self {
+    let (url, hash) = match url.find('#') {
+      Some(index) => url.split_at(index),
+      None => (url, ""),
+    };
+    let (pathname, search) = match url.find('?') {
+      Some(index) => url.split_at(index),
+      None => (url, ""),
+    };
+
+    Self {
+      pathname: pathname.to_owned(),
+      search: if search == "?" {
+        "".to_owned()
+      } else {
+        search.to_owned()
+      },
+      hash: if hash == "#" {
+        "".to_owned()
+      } else {
+        hash.to_owned()
+      },
+      state: None,
+      background: None,
+    }
+  }
+
+  pub fn parse_state<T>(&self) -> Result<Option<T>, serde_json::Error>
+  where
+    T: DeserializeOwned,
+  {
+    self.state.as_deref().map(serde_json::from_str).transpose()
+  }
+}
+
+impl From<&str> for Location {
+  fn from(url: &str) -> Self {
+    Self::parse(url)
+  }
+}
+
+impl std::fmt::Display for Location {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}{}{}", self.pathname, self.search, self.hash)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavigateOptions {
+  /// Replaces the current history entry instead of pushing a new one.
+  pub replace: bool,
+  /// Scrolls to the top of the page, or to the element the fragment points to, once the new
+  /// location is rendered. Defaults to `true`.
+  pub scroll: bool,
+  /// JSON encoded state stored with the history entry, read back with `Location::parse_state`.
+  pub state: Option<String>,
+  /// Keeps rendering this location in the `Routes` blocks, while the ones with `modal` render the
+  /// new location over it. The window does not scroll meanwhile.
+  pub background: Option<Location>,
+}
+
+impl Default for NavigateOptions {
+  fn default() -> Self {
+    Self {
+      replace: false,
+      scroll: true,
+      state: None,
+      background: None,
+    }
+  }
+}
+
+impl NavigateOptions {
+  pub fn replace(mut self, replace: bool) -> Self {
+    self.replace = replace;
+    self
+  }
+
+  pub fn scroll(mut self, scroll: bool) -> Self {
+    self.scroll = scroll;
+    self
+  }
+
+  pub fn state<T>(mut self, state: &T) -> Self
+  where
+    T: Serialize,
+  {
+    match serde_json::to_string(state) {
+      Ok(state) => self.state = Some(state),
+      Err(err) => tracing::error!("{:?}", &err),
+    }
+    self
+  }
+
+  /// Opens the new location over `location`. When `location` is itself open over a background,
+  /// that background is kept instead, so modals opened from a modal share the page underneath.
+  pub fn background(mut self, location: &Location) -> Self {
+    let mut background = location.background.as_deref().unwrap_or(location).clone();
+    background.background = None;
+    self.background = Some(background);
+    self
+  }
+}
+
+// A navigation requested through the router, kept aside while a `use_blocker` condition holds
+#[derive(Clone, Debug, PartialEq)]
+enum Navigation {
+  Go(isize),
+  Push(Location, Scroll),
+  Replace(Location, Scroll),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavigationEvent {
+  /// `None` for the location the router started with.
+  pub from: Option<Location>,
+  pub to: Location,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NavigationError {
+  /// A `use_blocker` condition held, the navigation waits for `proceed` or `reset`.
+  Blocked,
+  /// The browser history rejected the new entry. The router location changed anyway.
+  History(String),
+}
+
+impl std::fmt::Display for NavigationError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      NavigationError::Blocked => write!(f, "The navigation is blocked"),
+      NavigationError::History(error) => {
+        write!(f, "The browser history rejected the navigation: {}", error)
+      }
+    }
+  }
+}
+
+impl std::error::Error for NavigationError {}
+
+#[derive(Default)]
+struct Listeners {
+  before_navigate: Vec<(usize, Rc<dyn Fn(&NavigationEvent)>)>,
+  navigate: Vec<(usize, Rc<dyn Fn(&NavigationEvent)>)>,
+  navigate_error: Vec<(usize, Rc<dyn Fn(&NavigationEvent, &NavigationError)>)>,
+  next_id: usize,
+  queued: Vec<QueuedEvent>,
+}
+
+// An event for the `on_navigate` and `on_navigate_error` callbacks, held until the router element
+// renders again and the router context is no longer borrowed
+enum QueuedEvent {
+  Navigate(NavigationEvent),
+  NavigateError(NavigationEvent, NavigationError),
+}
+
+/// Keeps a callback registered on `RouterCore` subscribed, until it's dropped.
+pub struct Subscription {
+  id: usize,
+  listeners: Weak<RefCell<Listeners>>,
+}
+
+// Cloned out of the listeners before calling them, so they can subscribe and unsubscribe
+fn clone_callbacks<T: ?Sized>(listeners: &[(usize, Rc<T>)]) -> Vec<Rc<T>> {
+  listeners
+    .iter()
+    .map(|(_, callback)| callback.clone())
+    .collect()
+}
+
+impl Drop for Subscription {
+  fn drop(&mut self) {
+    if let Some(listeners) = self.listeners.upgrade() {
+      let mut listeners = listeners.borrow_mut();
+      listeners.before_navigate.retain(|(id, _)| *id != self.id);
+      listeners.navigate.retain(|(id, _)| *id != self.id);
+      listeners.navigate_error.retain(|(id, _)| *id != self.id);
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NavigationState {
+  Idle,
+  Loading,
+  Submitting,
+}
+
 #[derive(Clone)]
 pub struct RouterCore {
-  pub location: Option<String>,
+  pub location: Option<Location>,
+  base_path: String,
+  blocked: Option<Navigation>,
+  blockers: Blockers,
+  entries: Vec<Location>,
+  hash: bool,
+  index: usize,
+  listeners: Rc<RefCell<Listeners>>,
+  // The entries before it were pushed before the page was reloaded, the router only knows they
+  // exist. Their locations are read from the browser once it moves to them.
+  restored: usize,
+  // What the mounted components reported through `use_pending` and `use_submitting`
+  pending: Vec<(ScopeId, NavigationState)>,
+  routes_group: RoutesGroup,
+  // Incremented on every location change
+  navigation: usize,
+  // The entries the browser reported outside `base_path`, which no route matches
+  outside: HashSet<usize>,
+  #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+  scroll: Scroll,
   #[cfg(target_arch = "wasm32")]
-  history: web_sys::History,
+  history: Option<web_sys::History>,
 }
 
 impl RouterCore {
   pub fn new(location: &Option<&str>) -> Self {
+    Self::browser(location, false)
+  }
+
+  /// Creates a router that keeps the location in `location.hash` (`/#/posts/1`), for static hosts
+  /// that cannot rewrite unknown paths to `index.html`.
+  pub fn hash(location: &Option<&str>) -> Self {
+    Self::browser(location, true)
+  }
+
+  fn browser(location: &Option<&str>, hash: bool) -> Self {
     let location = match location {
-      Some(location) => Some(location.to_string()),
+      Some(location) => Some(Location::parse(location)),
       None => {
         #[cfg(target_arch = "wasm32")]
-        match web_sys::window() {
-          Some(window) => match window.location().pathname() {
-            Ok(pathname) => Some(pathname),
-            Err(_) => None,
-          },
-          None => None,
+        {
+          Self::browser_location(hash)
         }
 
         #[cfg(not(target_arch = "wasm32"))]
@@ -34,116 +286,858 @@ impl RouterCore {
       }
     };
 
-    Self {
+    #[allow(unused_mut)]
+    let mut router_core = Self {
+      base_path: "".to_owned(),
+      blocked: None,
+      blockers: Default::default(),
+      entries: location.iter().cloned().collect(),
+      hash,
+      index: 0,
+      listeners: Default::default(),
       location,
+      navigation: 0,
+      outside: HashSet::new(),
+      pending: vec![],
+      restored: 0,
+      routes_group: Default::default(),
+      scroll: Scroll::Restore,
       #[cfg(target_arch = "wasm32")]
-      history: web_sys::window().unwrap().history().unwrap(),
+      history: Some(web_sys::window().unwrap().history().unwrap()),
+    };
+
+    // Tag the entry the app was loaded with, so a later popstate back to it can be told apart from
+    // entries we know nothing about. The index and the state survive reloads, so they're kept: the
+    // entries pushed before the reload still carry their own indexes. The background is not kept:
+    // loading a modal location directly renders its full page version.
+    #[cfg(target_arch = "wasm32")]
+    if let Some(history) = router_core.history.clone() {
+      let HistoryState { index, state, .. } = history
+        .state()
+        .map(|state| parse_history_state(&state))
+        .unwrap_or_default();
+      for location in router_core
+        .location
+        .iter_mut()
+        .chain(&mut router_core.entries)
+      {
+        location.state = state.clone();
+      }
+      if let (Some(index), Some(location)) = (index, &router_core.location) {
+        router_core.entries = vec![location.clone(); index + 1];
+        router_core.index = index;
+        router_core.restored = index;
+      }
+
+      if let Err(err) = history.replace_state(&router_core.history_state(), "") {
+        tracing::error!("{:?}", &err);
+      }
     }
+
+    router_core
   }
-}
 
-impl RouterHistory for RouterCore {
-  fn push(&mut self, url: impl AsRef<str>) {
-    // #[cfg(not(target_arch = "wasm32"))]
+  #[cfg(target_arch = "wasm32")]
+  fn browser_location(hash: bool) -> Option<Location> {
+    let location = web_sys::window()?.location();
+
+    if hash {
+      match location.hash() {
+        Ok(hash) => match hash.trim_start_matches('#') {
+          "" => Some(Location::parse("/")),
+          url => Some(Location::parse(url)),
+        },
+        Err(_) => None,
+      }
+    } else {
+      Some(Location {
+        pathname: location.pathname().ok()?,
+        search: location.search().unwrap_or_default(),
+        hash: location.hash().unwrap_or_default(),
+        state: None,
+        background: None,
+      })
+    }
+  }
+
+  /// Mounts the app under `base_path`: locations read from the browser are stripped of it, and
+  /// hrefs and history entries written to the browser are prefixed with it.
+  pub fn with_base_path(mut self, base_path: &str) -> Self {
+    self.base_path = match base_path.trim_matches('/') {
+      "" => "".to_owned(),
+      base_path => format!("/{}", base_path),
+    };
+    self.outside = self
+      .entries
+      .iter()
+      .enumerate()
+      .filter(|(_, location)| !self.in_base_path(location))
+      .map(|(index, _)| index)
+      .collect();
+    self.location = self
+      .location
+      .take()
+      .map(|location| self.strip_base_path(location));
+    self.entries = std::mem::take(&mut self.entries)
+      .into_iter()
+      .map(|location| self.strip_base_path(location))
+      .collect();
+    self
+  }
+
+  pub fn base_path(&self) -> &str {
+    &self.base_path
+  }
+
+  // Whether `location`, read from the browser, is one of the app
+  fn in_base_path(&self, location: &Location) -> bool {
+    location
+      .pathname
+      .strip_prefix(&self.base_path)
+      .is_some_and(|pathname| pathname.is_empty() || pathname.starts_with('/'))
+  }
+
+  // Locations outside `base_path` are kept as they are, see `is_outside_base_path`.
+  fn strip_base_path(&self, mut location: Location) -> Location {
+    if self.in_base_path(&location) {
+      location.pathname = match &location.pathname[self.base_path.len()..] {
+        "" => "/".to_owned(),
+        pathname => pathname.to_owned(),
+      };
+    }
+
+    location
+  }
+
+  // Whether the browser is on a location outside `base_path`, where `Routes` render their fallback
+  fn is_outside_base_path(&self) -> bool {
+    self.outside.contains(&self.index)
+  }
+
+  /// The `href` a link to `url` should render, so opening it in a new tab lands on the same route.
+  pub fn href(&self, url: &str) -> String {
+    if is_external_url(url) {
+      return url.to_owned();
+    }
+
+    let url = if url.starts_with('/') {
+      format!("{}{}", self.base_path, url)
+    } else {
+      url.to_owned()
+    };
+
+    if self.hash {
+      format!("#{}", url)
+    } else {
+      url
+    }
+  }
+
+  /// Creates a router that keeps its history only in memory, without touching the browser. When
+  /// `entries` is empty the stack starts at `/`, and `index` defaults to the last entry.
+  pub fn memory(entries: &[&str], index: Option<usize>) -> Self {
+    let entries = if entries.is_empty() {
+      vec![Location::parse("/")]
+    } else {
+      entries.iter().map(|entry| Location::parse(entry)).collect()
+    };
+    let index = index.unwrap_or(entries.len() - 1).min(entries.len() - 1);
+
+    Self {
+      location: Some(entries[index].clone()),
+      base_path: "".to_owned(),
+      blocked: None,
+      blockers: Default::default(),
+      entries,
+      hash: false,
+      index,
+      listeners: Default::default(),
+      navigation: 0,
+      outside: HashSet::new(),
+      pending: vec![],
+      restored: 0,
+      routes_group: Default::default(),
+      scroll: Scroll::Keep,
+      #[cfg(target_arch = "wasm32")]
+      history: None,
+    }
+  }
+
+  // Stores the position in the stack together with the state and background of the current entry,
+  // see `parse_history_state`.
+  #[cfg(target_arch = "wasm32")]
+  fn history_state(&self) -> wasm_bindgen::JsValue {
+    let (state, background) = self.location.as_ref().map_or((None, None), |location| {
+      (
+        location.state.clone(),
+        location
+          .background
+          .as_ref()
+          .map(|background| background.to_string()),
+      )
+    });
+    wasm_bindgen::JsValue::from_str(
+      &serde_json::json!({ "index": self.index, "state": state, "background": background })
+        .to_string(),
+    )
+  }
+
+  /// Pushes or replaces `url` in the history stack as `options` asks, storing `options.state`
+  /// and `options.background` with the new entry.
+  pub fn navigate(&mut self, url: &str, options: &NavigateOptions) {
+    let mut location = Location::parse(url);
+    location.state = options.state.clone();
+    location.background = options.background.clone().map(Box::new);
+    let scroll = if options.scroll && options.background.is_none() {
+      Scroll::Top
+    } else {
+      Scroll::Keep
+    };
+
+    self.request(if options.replace {
+      Navigation::Replace(location, scroll)
+    } else {
+      Navigation::Push(location, scroll)
+    });
+  }
+
+  /// `Submitting` while a component reports it's submitting, otherwise `Loading` while one waits
+  /// for data, otherwise `Idle`.
+  pub fn navigation_state(&self) -> NavigationState {
+    if self
+      .pending
+      .iter()
+      .any(|(_, state)| *state == NavigationState::Submitting)
     {
-      self.location = Some(url.as_ref().to_owned());
+      NavigationState::Submitting
+    } else if self.pending.is_empty() {
+      NavigationState::Idle
+    } else {
+      NavigationState::Loading
+    }
+  }
+
+  /// Whether a mounted `use_blocker` hook currently blocks navigation.
+  pub fn is_blocking(&self) -> bool {
+    self
+      .blockers
+      .borrow()
+      .iter()
+      .any(|condition| condition.get())
+  }
+
+  /// The location of the navigation kept aside by a blocker, waiting for `proceed` or `reset`.
+  pub fn blocked_location(&self) -> Option<Location> {
+    match self.blocked.as_ref()? {
+      Navigation::Go(delta) => self
+        .entries
+        .get((self.index as isize + delta) as usize)
+        .cloned(),
+      Navigation::Push(location, _) | Navigation::Replace(location, _) => Some(location.clone()),
+    }
+  }
+
+  /// Runs the navigation kept aside by a blocker, even if the blocker still holds.
+  pub fn proceed(&mut self) {
+    if let Some(navigation) = self.blocked.take() {
+      self.run(navigation);
+    }
+  }
+
+  /// Drops the navigation kept aside by a blocker, staying on the current location.
+  pub fn reset(&mut self) {
+    self.blocked = None;
+  }
+
+  /// Calls `callback` before every location change. The router context is still being written, so
+  /// the callback can't read it.
+  pub fn on_before_navigate<F>(&self, callback: F) -> Subscription
+  where
+    F: Fn(&NavigationEvent) + 'static,
+  {
+    let mut listeners = self.listeners.borrow_mut();
+    let id = listeners.next_id;
+    listeners.next_id += 1;
+    listeners.before_navigate.push((id, Rc::new(callback)));
+    self.subscription(id)
+  }
+
+  /// Calls `callback` after every location change: push, replace, back/forward and the browser
+  /// buttons. The callback runs once the router element renders again, so it can read the router
+  /// context and navigate.
+  ///
+  /// The initial location is emitted once, on the router element's second render, so the callbacks
+  /// subscribed while its children mount receive it. Callbacks subscribed later don't.
+  pub fn on_navigate<F>(&self, callback: F) -> Subscription
+  where
+    F: Fn(&NavigationEvent) + 'static,
+  {
+    let mut listeners = self.listeners.borrow_mut();
+    let id = listeners.next_id;
+    listeners.next_id += 1;
+    listeners.navigate.push((id, Rc::new(callback)));
+    self.subscription(id)
+  }
+
+  /// Calls `callback` when a navigation is blocked or the browser history rejects it. Like
+  /// `on_navigate`, the callback runs once the router element renders again.
+  pub fn on_navigate_error<F>(&self, callback: F) -> Subscription
+  where
+    F: Fn(&NavigationEvent, &NavigationError) + 'static,
+  {
+    let mut listeners = self.listeners.borrow_mut();
+    let id = listeners.next_id;
+    listeners.next_id += 1;
+    listeners.navigate_error.push((id, Rc::new(callback)));
+    self.subscription(id)
+  }
+
+  fn subscription(&self, id: usize) -> Subscription {
+    Subscription {
+      id,
+      listeners: Rc::downgrade(&self.listeners),
+    }
+  }
+
+  fn navigation_event(&self, to: Location) -> NavigationEvent {
+    NavigationEvent {
+      from: self.location.clone(),
+      to,
+    }
+  }
+
+  fn queue_error(&self, event: &NavigationEvent, error: NavigationError) {
+    self
+      .listeners
+      .borrow_mut()
+      .queued
+      .push(QueuedEvent::NavigateError(event.clone(), error));
+  }
+
+  fn request(&mut self, navigation: Navigation) {
+    if self.is_blocking() {
+      tracing::trace!("Navigation blocked");
+      self.blocked = Some(navigation);
+      if let Some(location) = self.blocked_location() {
+        self.queue_error(&self.navigation_event(location), NavigationError::Blocked);
+      }
+    } else {
+      self.run(navigation);
+    }
+  }
+
+  fn run(&mut self, navigation: Navigation) {
+    match navigation {
+      Navigation::Go(delta) => {
+        if !self.go(delta) {
+          tracing::trace!(
+            "There is no entry {} steps away in the history stack",
+            delta
+          );
+          return;
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(Err(err)) = self
+          .history
+          .as_ref()
+          .map(|history| history.go_with_delta(delta as i32))
+        {
+          tracing::error!("{:?}", &err);
+        }
+      }
+      Navigation::Push(location, scroll) => self.push_location(location, scroll),
+      Navigation::Replace(location, scroll) => self.replace_location(location, scroll),
+    }
+  }
+
+  fn set_location(&mut self, location: Location, scroll: Scroll) {
+    let event = self.navigation_event(location.clone());
+    let callbacks = clone_callbacks(&self.listeners.borrow().before_navigate);
+    for callback in callbacks {
+      callback(&event);
+    }
+
+    self.location = Some(location);
+    self.navigation += 1;
+    self.scroll = scroll;
+
+    self
+      .listeners
+      .borrow_mut()
+      .queued
+      .push(QueuedEvent::Navigate(event));
+  }
+
+  fn push_location(&mut self, location: Location, scroll: Scroll) {
+    #[cfg(target_arch = "wasm32")]
+    let event = self.navigation_event(location.clone());
+
+    if !self.entries.is_empty() {
+      self.truncate_entries();
     }
+    self.entries.push(location.clone());
+    self.index = self.entries.len() - 1;
+    self.set_location(location, scroll);
 
     #[cfg(target_arch = "wasm32")]
-    if let Err(err) =
-      self
-        .history
-        .push_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(url.as_ref()))
-    {
+    if let Some(Err(err)) = self.history.as_ref().map(|history| {
+      history.push_state_with_url(&self.history_state(), "", Some(&self.current_href()))
+    }) {
       tracing::error!("{:?}", &err);
+      self.queue_error(&event, NavigationError::History(format!("{:?}", err)));
     }
   }
 
-  fn replace(&mut self, url: impl AsRef<str>) {
-    // #[cfg(not(target_arch = "wasm32"))]
-    {
-      self.location = Some(url.as_ref().to_owned());
+  fn replace_location(&mut self, location: Location, scroll: Scroll) {
+    #[cfg(target_arch = "wasm32")]
+    let event = self.navigation_event(location.clone());
+
+    match self.entries.get_mut(self.index) {
+      Some(entry) => *entry = location.clone(),
+      None => self.entries.push(location.clone()),
     }
+    self.outside.remove(&self.index);
+    self.set_location(location, scroll);
 
     #[cfg(target_arch = "wasm32")]
-    if let Err(err) =
-      self
-        .history
-        .replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(url.as_ref()))
-    {
+    if let Some(Err(err)) = self.history.as_ref().map(|history| {
+      history.replace_state_with_url(&self.history_state(), "", Some(&self.current_href()))
+    }) {
       tracing::error!("{:?}", &err);
+      self.queue_error(&event, NavigationError::History(format!("{:?}", err)));
+    }
+  }
+
+  // Drops the entries after the current one, before pushing a new one
+  fn truncate_entries(&mut self) {
+    self.entries.truncate(self.index + 1);
+    let index = self.index;
+    self.outside.retain(|outside| *outside <= index);
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn current_href(&self) -> String {
+    self.href(
+      &self
+        .location
+        .as_ref()
+        .map(|location| location.to_string())
+        .unwrap_or_default(),
+    )
+  }
+
+  /// Moves the history stack cursor by `delta` entries, keeping `location` in sync. Returns `false`
+  /// when the target entry does not exist, in which case nothing changes.
+  fn go(&mut self, delta: isize) -> bool {
+    let index = self.index as isize + delta;
+
+    if index < 0 || index as usize >= self.entries.len() {
+      return false;
+    }
+
+    self.index = index as usize;
+    // The location of an entry restored after a reload comes with the browser's popstate
+    if self.index >= self.restored {
+      self.set_location(self.entries[self.index].clone(), Scroll::Restore);
+    }
+    true
+  }
+
+  /// Syncs the history stack after the browser moved to another entry on its own (back/forward
+  /// buttons). `location` is the one read from the browser, still including the base path. `index`
+  /// is the position stored in `history.state` by `push`/`replace`, when present.
+  ///
+  /// While a blocker holds, the browser is sent back to the current entry and the move is kept
+  /// aside like any other navigation. Entries without an index can't be reverted and go through.
+  ///
+  /// The browser also reports the moves the router asked for itself (`back`, `forward`, `proceed`
+  /// and the revert of a blocked move), which are already applied and don't emit events again.
+  pub fn pop_state(&mut self, location: Location, index: Option<usize>) {
+    let outside = !self.in_base_path(&location);
+    let location = self.strip_base_path(location);
+
+    // The echo of a move the router made itself with `history.go`, already applied by `go`
+    if index == Some(self.index) && self.location.as_ref() == Some(&location) {
+      tracing::trace!("The router is already on {}", location);
+      return;
+    }
+
+    if let Some(index) = index {
+      if index != self.index && self.is_blocking() {
+        let delta = index as isize - self.index as isize;
+        tracing::trace!("Navigation blocked");
+        self.blocked = Some(Navigation::Go(delta));
+        self.queue_error(&self.navigation_event(location), NavigationError::Blocked);
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(Err(err)) = self
+          .history
+          .as_ref()
+          .map(|history| history.go_with_delta(-delta as i32))
+        {
+          tracing::error!("{:?}", &err);
+        }
+        return;
+      }
+    }
+
+    match index {
+      Some(index) => {
+        // Forward entries pushed before a reload, the router learns about them as it reaches them
+        if index >= self.entries.len() {
+          self.entries.resize(index + 1, location.clone());
+        }
+        self.index = index;
+        self.entries[index] = location.clone();
+      }
+      None => {
+        if !self.entries.is_empty() {
+          self.truncate_entries();
+        }
+        self.entries.push(location.clone());
+        self.index = self.entries.len() - 1;
+      }
+    }
+    if outside {
+      self.outside.insert(self.index);
+    } else {
+      self.outside.remove(&self.index);
     }
+
+    self.set_location(location, Scroll::Restore);
+  }
+}
+
+impl RouterHistory for RouterCore {
+  fn push(&mut self, url: impl AsRef<str>) {
+    self.request(Navigation::Push(
+      Location::parse(url.as_ref()),
+      Scroll::Keep,
+    ));
+  }
+
+  fn replace(&mut self, url: impl AsRef<str>) {
+    self.request(Navigation::Replace(
+      Location::parse(url.as_ref()),
+      Scroll::Keep,
+    ));
   }
 
   fn back(&mut self) {
-    todo!()
+    if !self.can_go_back() {
+      tracing::trace!("There is no previous entry in the history stack");
+      return;
+    }
+
+    self.request(Navigation::Go(-1));
+  }
+
+  fn can_go_back(&self) -> bool {
+    self.index > 0
+  }
+
+  fn can_go_forward(&self) -> bool {
+    self.index + 1 < self.entries.len()
   }
 
   fn forward(&mut self) {
-    todo!()
+    if !self.can_go_forward() {
+      tracing::trace!("There is no next entry in the history stack");
+      return;
+    }
+
+    self.request(Navigation::Go(1));
   }
 }
 
 #[derive(Clone)]
 pub struct RoutesContext {
   active_class: String,
-  base_path: String,
   fallback: Option<Component>,
-  router: matchit::Router<Component>,
+  router: matchit::Router<Vec<RouteDefinition>>,
+  // The named outlet elements of every route, by route absolute path
+  slots: HashMap<String, Vec<(String, Component)>>,
 }
 
 impl RoutesContext {
-  pub fn new(base_path: &str, active_class: &Option<&str>) -> Self {
+  pub fn new(active_class: &Option<&str>) -> Self {
     Self {
       active_class: active_class.map_or_else(|| "active".to_owned(), |s| s.to_owned()),
-      base_path: base_path.to_owned(),
       fallback: None,
       router: matchit::Router::new(),
+      slots: HashMap::new(),
     }
   }
 }
 
-#[allow(non_snake_case)]
-#[inline_props]
-pub fn BrowserRouter<'a>(
-  cx: Scope<'a>,
+#[derive(Props)]
+pub struct RouterProps<'a> {
+  #[props(default)]
+  base_path: &'a str,
   location: Option<&'a str>,
   children: Element<'a>,
-) -> Element<'a> {
-  use_context_provider(&cx, || RouterCore::new(&location));
-  // let context = use_context::<RouterCore>(&cx)?;
-  // cx.use_hook(|| {
-  //   // TODO: figure out how to update the location
-  //   #[cfg(target_arch = "wasm32")]
-  //   {
-  //     let context = std::sync::Arc::new(context);
-  //     gloo::events::EventListener::new(&web_sys::window().unwrap(), "popstate", move |_| {
-  //       let location = web_sys::window().unwrap().location().pathname().unwrap();
-  //       tracing::trace!("window.popstate. Location {}", location);
-  //       context.read().replace(location);
-  //     });
-  //   }
-  // });
-  cx.render(rsx!(children))
+  // Saves the scroll position of every history entry and restores it on back/forward, scrolls to
+  // the top (or to the element targeted by the fragment) on push
+  #[props(default = true)]
+  scroll_restoration: bool,
 }
 
 #[allow(non_snake_case)]
-#[inline_props]
-pub fn Fallback<'a>(cx: Scope<'a>, element: Component, children: Element<'a>) -> Element {
-  let context = use_context::<RoutesContext>(&cx)?;
-  let _ = children;
+pub fn BrowserRouter<'a>(cx: Scope<'a, RouterProps<'a>>) -> Element<'a> {
+  let RouterProps {
+    base_path,
+    location,
+    children,
+    scroll_restoration,
+  } = &cx.props;
+  use_context_provider(&cx, || RouterCore::new(location).with_base_path(base_path));
+  use_context_provider(&cx, ModifierKeys::default);
+  let context = use_context::<RouterCore>(&cx)?;
 
-  cx.use_hook(|| {
-    tracing::trace!("Registering fallback component");
-    context.write().fallback = Some(element.clone());
-  });
+  // The listener is kept in the hook, so it lives (and is removed) with the `BrowserRouter` scope.
+  #[cfg(target_arch = "wasm32")]
+  {
+    cx.use_hook(|| history_listener(context.clone(), "popstate"));
+    let restoration =
+      cx.use_hook(|| scroll_restoration.then(|| ScrollRestoration::new(context.clone())));
+    if let Some(restoration) = restoration {
+      restoration.update(&context.read());
+    }
+  }
+  #[cfg(not(target_arch = "wasm32"))]
+  let _ = scroll_restoration;
+
+  use_queued_events(&cx, context);
+  cx.render(rsx!(
+    ModifierKeysListener {}
+    children
+  ))
+}
+
+#[allow(non_snake_case)]
+pub fn HashRouter<'a>(cx: Scope<'a, RouterProps<'a>>) -> Element<'a> {
+  let RouterProps {
+    base_path,
+    location,
+    children,
+    scroll_restoration,
+  } = &cx.props;
+  use_context_provider(&cx, || RouterCore::hash(location).with_base_path(base_path));
+  use_context_provider(&cx, ModifierKeys::default);
+  let context = use_context::<RouterCore>(&cx)?;
+
+  #[cfg(target_arch = "wasm32")]
+  {
+    cx.use_hook(|| history_listener(context.clone(), "hashchange"));
+    let restoration =
+      cx.use_hook(|| scroll_restoration.then(|| ScrollRestoration::new(context.clone())));
+    if let Some(restoration) = restoration {
+      restoration.update(&context.read());
+    }
+  }
+  #[cfg(not(target_arch = "wasm32"))]
+  let _ = scroll_restoration;
+
+  use_queued_events(&cx, context);
+  cx.render(rsx!(
+    ModifierKeysListener {}
+    children
+  ))
+}
+
+// Whether a modifier key is held, in which case the links leave their clicks to the browser
+#[derive(Clone, Copy, Default, PartialEq)]
+struct ModifierKeys(bool);
+
+// Keeps `ModifierKeys` in sync with the keyboard. It's subscribed to it in its own scope, so a key
+// press re-renders the links but not the router children.
+#[allow(non_snake_case, unused_variables)]
+fn ModifierKeysListener(cx: Scope) -> Element {
+  #[cfg(target_arch = "wasm32")]
+  {
+    let context = use_context::<ModifierKeys>(&cx)?;
+    cx.use_hook(|| {
+      ["keydown", "keyup", "blur"].map(|event| {
+        let context = context.clone();
+        gloo::events::EventListener::new(&web_sys::window().unwrap(), event, move |event| {
+          use wasm_bindgen::JsCast;
+
+          // Keys released while the window had no focus are not reported, `blur` resets them.
+          let held = event
+            .dyn_ref::<web_sys::KeyboardEvent>()
+            .is_some_and(|event| {
+              event.alt_key() || event.ctrl_key() || event.meta_key() || event.shift_key()
+            });
+          if context.read().0 != held {
+            context.write().0 = held;
+          }
+        })
+      })
+    });
+  }
+
+  None
+}
+
+// Writing to the shared `RouterCore` re-renders every component reading the router context.
+#[cfg(target_arch = "wasm32")]
+fn history_listener(
+  context: UseSharedState<RouterCore>,
+  event: &'static str,
+) -> gloo::events::EventListener {
+  gloo::events::EventListener::new(&web_sys::window().unwrap(), event, move |_| {
+    let hash = context.read().hash;
+    let mut location = match RouterCore::browser_location(hash) {
+      Some(location) => location,
+      None => {
+        tracing::error!("Unable to read the location from `window.location`");
+        return;
+      }
+    };
+    let HistoryState {
+      background,
+      index,
+      state,
+    } = web_sys::window()
+      .unwrap()
+      .history()
+      .and_then(|history| history.state())
+      .map(|state| parse_history_state(&state))
+      .unwrap_or_default();
+    location.state = state;
+    location.background = background.map(Box::new);
+    tracing::trace!("window.{}. Location {}", event, location);
+    context.write().pop_state(location, index);
+  })
+}
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+struct HistoryState {
+  background: Option<Location>,
+  index: Option<usize>,
+  state: Option<String>,
+}
+
+// `history.state` is the JSON written by `RouterCore::history_state`, anything else (entries
+// pushed outside the router) has neither an index, a state nor a background.
+#[cfg(target_arch = "wasm32")]
+fn parse_history_state(state: &wasm_bindgen::JsValue) -> HistoryState {
+  match state
+    .as_string()
+    .and_then(|state| serde_json::from_str::<serde_json::Value>(&state).ok())
+  {
+    Some(value) => HistoryState {
+      background: value["background"].as_str().map(Location::parse),
+      index: value["index"].as_u64().map(|index| index as usize),
+      state: value["state"].as_str().map(|state| state.to_owned()),
+    },
+    None => HistoryState::default(),
+  }
+}
+
+// Called by the router elements on every render. The first render queues the initial location and
+// schedules the next one, so the children subscribed to `on_navigate` by the time it is emitted.
+fn use_queued_events(cx: &ScopeState, context: &UseSharedState<RouterCore>) {
+  let created = cx.use_hook(|| Cell::new(true));
+  if created.replace(false) {
+    let router_core = context.read();
+    if let Some(location) = &router_core.location {
+      router_core
+        .listeners
+        .borrow_mut()
+        .queued
+        .push(QueuedEvent::Navigate(NavigationEvent {
+          from: None,
+          to: location.clone(),
+        }));
+    }
+    cx.needs_update();
+  } else {
+    emit_queued_events(context);
+  }
+}
+
+// Writing the router context re-renders the router elements, so the events are emitted after the
+// write is done.
+fn emit_queued_events(context: &UseSharedState<RouterCore>) {
+  let listeners = context.read().listeners.clone();
+  let queued = std::mem::take(&mut listeners.borrow_mut().queued);
+
+  for event in queued {
+    match event {
+      QueuedEvent::Navigate(event) => {
+        let callbacks = clone_callbacks(&listeners.borrow().navigate);
+        for callback in callbacks {
+          callback(&event);
+        }
+      }
+      QueuedEvent::NavigateError(event, error) => {
+        let callbacks = clone_callbacks(&listeners.borrow().navigate_error);
+        for callback in callbacks {
+          callback(&event, &error);
+        }
+      }
+    }
+  }
+}
+
+#[derive(Props)]
+pub struct MemoryRouterProps<'a> {
+  #[props(default)]
+  initial_entries: &'a [&'a str],
+  initial_index: Option<usize>,
+  children: Element<'a>,
+}
+
+#[allow(non_snake_case)]
+pub fn MemoryRouter<'a>(cx: Scope<'a, MemoryRouterProps<'a>>) -> Element<'a> {
+  let MemoryRouterProps {
+    initial_entries,
+    initial_index,
+    children,
+  } = &cx.props;
+  use_context_provider(&cx, || RouterCore::memory(initial_entries, *initial_index));
+  let context = use_context::<RouterCore>(&cx)?;
+
+  use_queued_events(&cx, context);
+  cx.render(rsx!(children))
+}
+
+#[allow(non_snake_case)]
+#[inline_props]
+pub fn Fallback<'a>(cx: Scope<'a>, element: Component, children: Element<'a>) -> Element {
+  let context = use_context::<RoutesContext>(&cx)?;
+  let _ = children;
+
+  cx.use_hook(|| {
+    tracing::trace!("Registering fallback component");
+    context.write().fallback = Some(element.clone());
+  });
 
   None
 }
 
+#[derive(Props, PartialEq)]
+pub struct ErrorProps {
+  pub error: String,
+}
+
+/// Replaces the default error box the router renders, provided as a context, e.g. with
+/// `cx.provide_context(ErrorElement(MyError))` in the app root.
+#[derive(Clone, Copy)]
+pub struct ErrorElement(pub Component<ErrorProps>);
+
 #[allow(non_snake_case)]
 #[inline_props]
 fn InternalError(cx: Scope<'a>, error: String) -> Element {
+  if let Some(ErrorElement(C)) = cx.consume_context::<ErrorElement>() {
+    return cx.render(rsx!(C {
+      error: error.clone()
+    }));
+  }
+
   cx.render(rsx!(
     div {
       style: r#"
@@ -189,56 +1183,204 @@ fn InternalError(cx: Scope<'a>, error: String) -> Element {
   ))
 }
 
+// Urls with a scheme (`https:`, `mailto:`, ...) or protocol relative ones leave the app
+fn is_external_url(url: &str) -> bool {
+  url.starts_with("//")
+    || url.split_once(':').is_some_and(|(scheme, _)| {
+      scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+// The router only handles clicks on internal paths opened in the same frame, downloads and
+// everything else are followed by the browser
+fn is_router_link(to: &str, target: &str) -> bool {
+  !is_external_url(to) && target == "_self"
+}
+
+// `prevent_default` can't depend on the event. The links drop it while `ModifierKeys` sees a
+// modifier key held, so the browser opens a new tab (Ctrl/Cmd) or window (Shift), or downloads
+// (Alt). A click with a modifier key `ModifierKeys` missed, e.g. under `MemoryRouter`, is replayed
+// here in a new tab, whatever the key. Middle and right clicks don't emit `click` at all.
+#[allow(unused_variables)]
+fn open_in_browser(href: &str) {
+  #[cfg(target_arch = "wasm32")]
+  if let Some(Err(err)) =
+    web_sys::window().map(|window| window.open_with_url_and_target(href, "_blank"))
+  {
+    tracing::error!("{:?}", &err);
+  }
+}
+
+#[derive(Props)]
+pub struct LinkProps<'a> {
+  #[props(into)]
+  to: String,
+  children: Element<'a>,
+  download: Option<&'a str>,
+  // Opens `to` over the current location, see `NavigateOptions::background`
+  #[props(default)]
+  modal: bool,
+  #[props(default)]
+  replace: bool,
+  #[props(default = true)]
+  scroll: bool,
+  // Stored with the history entry, see `NavigateOptions::state`
+  state: Option<serde_json::Value>,
+  #[props(default = "_self")]
+  target: &'a str,
+}
+
+/// Navigates to `to` on click. A click with a modifier key is left to the browser, like on any
+/// other link: Ctrl/Cmd opens a new tab, Shift a new window and Alt downloads `to`.
 #[allow(non_snake_case)]
-#[inline_props]
-pub fn Link<'a>(cx: Scope<'a>, to: &'a str, children: Element<'a>) -> Element {
+pub fn Link<'a>(cx: Scope<'a, LinkProps<'a>>) -> Element {
+  let LinkProps {
+    to,
+    children,
+    download,
+    modal,
+    replace,
+    scroll,
+    state,
+    target,
+  } = &cx.props;
+  let router_core = use_context::<RouterCore>(&cx)?;
+  let modifier_keys = use_context::<ModifierKeys>(&cx);
+  let to = use_route_base(&cx).borrow().resolve(to);
+  let href = router_core.read().href(&to);
   let navigate = use_navigate(&cx);
+  let options = link_options(&router_core.read(), *modal, *replace, *scroll, state);
+
+  if let Some(download) = download {
+    return cx.render(rsx!(a {
+      href: "{href}",
+      target: "{target}",
+      download: "{download}",
+      children
+    }));
+  }
+
+  let router_link = is_router_link(&to, target) && !modifier_keys.is_some_and(|keys| keys.read().0);
+  let prevent_default = if router_link { "onclick" } else { "" };
+  let new_tab_href = href.clone();
   cx.render(rsx!(a {
-    href: "{to}",
-    prevent_default: "onclick",
-    onclick: move |_| navigate(to),
+    href: "{href}",
+    target: "{target}",
+    prevent_default: "{prevent_default}",
+    onclick: move |evt: MouseEvent| {
+      if router_link {
+        if evt.data.modifiers().is_empty() {
+          navigate(&to, options.clone());
+        } else {
+          open_in_browser(&new_tab_href);
+        }
+      }
+    },
     children
   }))
 }
 
-#[allow(non_snake_case)]
-#[inline_props]
-pub fn NavLink<'a>(
-  cx: Scope<'a>,
-  to: &'a str,
+#[derive(Props)]
+pub struct NavLinkProps<'a> {
+  #[props(into)]
+  to: String,
   active_class: Option<&'a str>,
   children: Element<'a>,
-) -> Element {
+  download: Option<&'a str>,
+  // Only matches when the location is exactly `to`, instead of `to` or any path below it
+  #[props(default)]
+  end: bool,
+  inactive_class: Option<&'a str>,
+  // Replaces the default matching, receives the current location and the resolved `to`
+  is_active: Option<fn(&Location, &str) -> bool>,
+  // Opens `to` over the current location, see `NavigateOptions::background`
+  #[props(default)]
+  modal: bool,
+  #[props(default)]
+  replace: bool,
+  #[props(default = true)]
+  scroll: bool,
+  // Stored with the history entry, see `NavigateOptions::state`
+  state: Option<serde_json::Value>,
+  #[props(default = "_self")]
+  target: &'a str,
+}
+
+#[allow(non_snake_case)]
+pub fn NavLink<'a>(cx: Scope<'a, NavLinkProps<'a>>) -> Element {
+  let NavLinkProps {
+    to,
+    active_class,
+    children,
+    download,
+    end,
+    inactive_class,
+    is_active,
+    modal,
+    replace,
+    scroll,
+    state,
+    target,
+  } = &cx.props;
   let router_core = use_context::<RouterCore>(&cx)?;
+  let modifier_keys = use_context::<ModifierKeys>(&cx);
   let context = use_context::<RoutesContext>(&cx);
+  let to = use_route_base(&cx).borrow().resolve(to);
 
   if let Some(context) = context {
-    let active_router = use_state(&cx, || {
-      let mut active_router = matchit::Router::new();
-      active_router.insert(to.to_string(), true).unwrap();
-      active_router
-    });
-    let class = if active_router
-      .get()
-      .at(
-        &router_core
-          .read()
-          .location
-          .clone()
-          .unwrap_or_else(|| "".to_owned()),
+    let active = router_core
+      .read()
+      .location
+      .as_ref()
+      .is_some_and(|location| match is_active {
+        Some(is_active) => is_active(location, &to),
+        None => is_active_path(&location.pathname, &to, *end),
+      });
+    let (class, aria_current) = if active {
+      (
+        active_class.map_or_else(|| context.read().active_class.clone(), |s| s.to_owned()),
+        "page",
       )
-      .is_ok()
-    {
-      active_class.map_or_else(|| context.read().active_class.clone(), |s| s.to_owned())
     } else {
-      "".to_owned()
+      (inactive_class.unwrap_or_default().to_owned(), "false")
     };
+    let href = router_core.read().href(&to);
     let navigate = use_navigate(&cx);
+    let options = link_options(&router_core.read(), *modal, *replace, *scroll, state);
+
+    if let Some(download) = download {
+      return cx.render(rsx!(a {
+        href: "{href}",
+        class: "{class}",
+        aria_current: "{aria_current}",
+        target: "{target}",
+        download: "{download}",
+        children
+      }));
+    }
+
+    let router_link =
+      is_router_link(&to, target) && !modifier_keys.is_some_and(|keys| keys.read().0);
+    let prevent_default = if router_link { "onclick" } else { "" };
+    let new_tab_href = href.clone();
     cx.render(rsx!(a {
-      href: "{to}",
+      href: "{href}",
       class: "{class}",
-      prevent_default: "onclick",
-      onclick: move |_| navigate(to),
+      aria_current: "{aria_current}",
+      target: "{target}",
+      prevent_default: "{prevent_default}",
+      onclick: move |evt: MouseEvent| {
+        if router_link {
+          if evt.data.modifiers().is_empty() {
+            navigate(&to, options.clone());
+          } else {
+            open_in_browser(&new_tab_href);
+          }
+        }
+      },
       children
     }))
   } else {
@@ -248,106 +1390,1514 @@ pub fn NavLink<'a>(
   }
 }
 
+// What a click on `Link` or `NavLink` navigates with, a modal one opens over the current location
+fn link_options(
+  router_core: &RouterCore,
+  modal: bool,
+  replace: bool,
+  scroll: bool,
+  state: &Option<serde_json::Value>,
+) -> NavigateOptions {
+  let options = NavigateOptions {
+    replace,
+    scroll,
+    state: state.as_ref().map(|state| state.to_string()),
+    background: None,
+  };
+
+  match &router_core.location {
+    Some(location) if modal => options.background(location),
+    _ => options,
+  }
+}
+
+// Compares path segments literally, so `:` and `*` in `to` are not treated as params. The root
+// path only matches exactly, otherwise it would be active for every location.
+fn is_active_path(pathname: &str, to: &str, end: bool) -> bool {
+  let to = to.split(&['?', '#'][..]).next().unwrap_or_default();
+  let to = to.trim_end_matches('/');
+  let pathname = pathname.trim_end_matches('/');
+
+  if end || to.is_empty() {
+    pathname == to
+  } else {
+    pathname
+      .strip_prefix(to)
+      .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+  }
+}
+
 #[derive(Props)]
 pub struct RoutesProps<'a> {
   active_class: Option<&'a str>,
-  #[props(default)]
-  base_path: &'a str,
   children: Element<'a>,
+  // Rendered when the matched route reports an error that no route `error_element` handled
+  error_element: Option<Component>,
+  // Matches the current location only while it's open over a background, which the blocks without
+  // `modal` keep rendering. `Routes` nested in its elements follow it.
+  #[props(default)]
+  modal: bool,
+  routable: Option<RoutableRoutes>,
 }
 
 #[allow(non_snake_case)]
 pub fn Routes<'a>(cx: Scope<'a, RoutesProps<'a>>) -> Element<'a> {
   let RoutesProps {
     active_class,
-    base_path,
     children,
+    error_element,
+    modal,
+    routable,
   } = &cx.props;
-  use_context_provider(&cx, || RoutesContext::new(base_path, active_class));
+  use_context_provider(&cx, || RoutesContext::new(active_class));
   let context = use_context::<RoutesContext>(&cx)?;
+
+  let modal = *cx.use_hook(|| {
+    if *modal {
+      cx.provide_context(ModalRoutes);
+    }
+    cx.consume_context::<ModalRoutes>().is_some()
+  });
+
+  cx.use_hook(|| {
+    for (path, element) in routable.map(|routes| routes()).unwrap_or_default() {
+      let element = match element {
+        Some(element) => element,
+        None => continue,
+      };
+
+      tracing::trace!("Registering routable route: {}", path);
+      if let Err(err) = context.write().router.insert(
+        path,
+        vec![RouteDefinition {
+          absolute_path: path.to_owned(),
+          element: Some(element),
+          ..Default::default()
+        }],
+      ) {
+        tracing::error!("{:?}", err);
+      }
+    }
+  });
+
+  use_context_provider(&cx, LoaderCache::default);
+  let cache = use_context::<LoaderCache>(&cx)?;
+  let started = cx.use_hook(|| RefCell::new((0, HashSet::new())));
+  use_navigation_state(
+    &cx,
+    (!cache.read().loading.is_empty()).then_some(NavigationState::Loading),
+    false,
+  );
+
   let router_core = use_context::<RouterCore>(&cx)?;
-  let base_path = &context.read().base_path;
+
+  let block = cx.use_hook(|| {
+    let group = cx
+      .consume_context::<RoutesGroup>()
+      .unwrap_or_else(|| router_core.read().routes_group.clone());
+    // `Routes` nested in the matched elements coordinate among themselves.
+    cx.provide_context(RoutesGroup::default());
+    RoutesBlockHook::new(group, cx.scope_id(), cx.schedule_update_any())
+  });
+
+  use_context_provider(&cx, MatchedRoutes::default);
+  let matched_routes = use_context::<MatchedRoutes>(&cx)?;
+
+  // A location opened over a background is rendered only by the modal blocks, the others keep
+  // rendering the background.
+  let (location, hidden) = match router_core.read().location.clone() {
+    Some(location) if modal => {
+      let hidden = location.background.is_none();
+      (Some(location), hidden)
+    }
+    Some(Location {
+      background: Some(background),
+      ..
+    }) => (Some(*background), false),
+    location => (location, false),
+  };
+
+  // Routes declared here are not nested in the route an enclosing `Routes` matched. Provided on
+  // every render, with the location this block renders.
+  cx.provide_context(RouteContext {
+    location: location.clone(),
+    ..Default::default()
+  });
+
+  // Outside the base path no route matches, the fallback renders instead.
+  let outside = router_core.read().is_outside_base_path();
+  let matched = match &location {
+    Some(location) if !hidden && !outside => match context.read().router.at(&location.pathname) {
+      Ok(res) => {
+        tracing::trace!("A route matched");
+        Some(MatchedRoutes {
+          guards: HashMap::new(),
+          location: location.clone(),
+          params: RouteParams::from(&res.params),
+          routes: res.value.clone(),
+        })
+      }
+      Err(err) => {
+        tracing::error!("{:?}", &err);
+        None
+      }
+    },
+    _ => None,
+  };
+
+  // The loaders of the whole matched chain start here together, instead of each route waiting
+  // for its parent element to render.
+  if let (Some(location), Some(MatchedRoutes { params, routes, .. })) = (&location, &matched) {
+    let mut started = started.borrow_mut();
+    if started.0 != router_core.read().navigation {
+      *started = (router_core.read().navigation, HashSet::new());
+    }
+
+    for route in routes.iter() {
+      let loader = match &route.loader {
+        Some(loader) => loader.clone(),
+        None => continue,
+      };
+      let key = loader_key(&route.absolute_path, location);
+      if !started.1.insert(key.clone()) {
+        continue;
+      }
+
+      tracing::trace!("Running the loader of {} for {}", key.0, key.1);
+      cache.write().loading.insert(key.clone());
+      let future = (loader.0)(params.clone(), location.clone());
+      let cache = cache.clone();
+      cx.push_future(async move {
+        let data = future.await;
+        let mut cache = cache.write();
+        cache.loading.remove(&key);
+        cache.data.insert(key, data);
+      });
+    }
+  }
+
+  // The guard results are kept while the match stays the same.
+  let current = matched.clone().unwrap_or_default();
+  if !matched_routes.read().same_match(&current) {
+    *matched_routes.write() = current;
+  }
+  block.set(
+    matched.is_some(),
+    context.read().fallback.is_some() && !hidden,
+  );
 
   cx.render(rsx!(
     children
 
-    match router_core.read().location.as_ref() {
-      Some(location) => match context
-        .read()
-        .router
-        .at(location.as_str().trim_start_matches(base_path))
-      {
-        Ok(res) => {
-          tracing::trace!("A route matched");
-          let C = res.value.clone();
-          rsx!( C {} )
-        }
-        Err(err) => {
-          tracing::error!("{:?}", &err);
-          match context.read().fallback {
-            Some(Fallback) => rsx!(Fallback {}),
-            None => rsx!(Fragment {}),
-          }
-        }
+    match (location, matched) {
+      (_, Some(MatchedRoutes { params, routes, .. })) => match error_element {
+        Some(C) => rsx!(ErrorBoundary {
+          fallback: *C,
+          RouteMatch { depth: 0, params: params, routes: routes }
+        }),
+        None => rsx!(ErrorBoundary {
+          RouteMatch { depth: 0, params: params, routes: routes }
+        }),
       },
-      None => {
+      (Some(_), None) => match context.read().fallback {
+        Some(Fallback) if block.renders_fallback() => rsx!(Fallback {}),
+        _ => rsx!(Fragment {}),
+      },
+      (None, None) => {
         rsx!(InternalError { error: "`RouterCore.location` is not set".to_owned() })
       }
     }
   ))
 }
 
-#[derive(Clone)]
-pub struct RouteContext {
-  absolute_path: String,
-  relative_path: String,
+// Provided by a `Routes` with `modal`, for itself and the blocks nested in its elements
+#[derive(Clone, Copy)]
+struct ModalRoutes;
+
+// The route chain and params the closest `Routes` matched, for the named outlets, with the location
+// it matched them against and what the guards of the chain resolved to, by route path
+#[derive(Clone, Default, PartialEq)]
+struct MatchedRoutes {
+  guards: HashMap<String, bool>,
+  location: Location,
+  params: RouteParams,
+  routes: Vec<RouteDefinition>,
 }
 
-#[allow(non_snake_case)]
-#[inline_props]
-pub fn Route<'a>(
-  cx: Scope<'a>,
-  path: &'a str,
-  children: Element<'a>,
-  element: Component,
-) -> Element<'a> {
-  let context = use_context::<RoutesContext>(&cx)?;
-  let error = use_state(&cx, || None);
+impl MatchedRoutes {
+  fn same_match(&self, other: &MatchedRoutes) -> bool {
+    self.location == other.location && self.params == other.params && self.routes == other.routes
+  }
+}
 
-  cx.use_hook(|| {
-    tracing::trace!("Registering route: {}", path);
-    let absolute_path = match cx.consume_context::<RouteContext>() {
-      Some(parent_context) => format!(
-        "{}/{}",
-        parent_context.absolute_path.trim_end_matches("/"),
-        path
-      ),
-      None => path.to_string(),
-    };
+// The `Routes` blocks rendered side by side, in mount order. Each renders what it matched, and
+// only the first one with a `Fallback` renders it, once none of them matched.
+#[derive(Clone, Default)]
+struct RoutesGroup(Rc<RefCell<Vec<RoutesBlock>>>);
 
-    let route_context = cx.provide_context(RouteContext {
-      absolute_path,
-      relative_path: path.to_string(),
+struct RoutesBlock {
+  fallback: bool,
+  matched: bool,
+  scope: ScopeId,
+}
+
+struct RoutesBlockHook {
+  group: RoutesGroup,
+  scope: ScopeId,
+  update: Arc<dyn Fn(ScopeId)>,
+}
+
+impl RoutesBlockHook {
+  fn new(group: RoutesGroup, scope: ScopeId, update: Arc<dyn Fn(ScopeId)>) -> Self {
+    group.0.borrow_mut().push(RoutesBlock {
+      fallback: false,
+      matched: false,
+      scope,
     });
 
-    if let Err(err) = context
-      .write()
-      .router
-      .insert(route_context.absolute_path, element.clone())
+    Self {
+      group,
+      scope,
+      update,
+    }
+  }
+
+  fn set(&self, matched: bool, fallback: bool) {
+    let changed = match self
+      .group
+      .0
+      .borrow_mut()
+      .iter_mut()
+      .find(|block| block.scope == self.scope)
     {
-      tracing::error!("{:?}", err);
-      error.set(Some(err.to_string()));
+      Some(block) if block.matched != matched || block.fallback != fallback => {
+        block.matched = matched;
+        block.fallback = fallback;
+        true
+      }
+      _ => false,
+    };
+
+    if changed {
+      self.update_others();
     }
-  });
+  }
 
-  cx.render(rsx!(
-    children
-    error.get().as_ref().map(|e| rsx!(InternalError { error: e.clone() }))
-  ))
+  fn renders_fallback(&self) -> bool {
+    let blocks = self.group.0.borrow();
+    !blocks.iter().any(|block| block.matched)
+      && blocks
+        .iter()
+        .find(|block| block.fallback)
+        .is_some_and(|block| block.scope == self.scope)
+  }
+
+  fn update_others(&self) {
+    for block in self.group.0.borrow().iter() {
+      if block.scope != self.scope {
+        (self.update)(block.scope);
+      }
+    }
+  }
 }
 
-pub fn use_navigate(cx: &ScopeState) -> impl FnOnce(&str) + '_ + Copy {
-  let context = use_context::<RouterCore>(&cx)
-    .expect("`use_navigate` can be used in components wraped by `BrowserRouter`");
-  move |path| context.write().push(path)
+impl Drop for RoutesBlockHook {
+  fn drop(&mut self) {
+    self
+      .group
+      .0
+      .borrow_mut()
+      .retain(|block| block.scope != self.scope);
+    self.update_others();
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RouteParams(Vec<(String, String)>);
+
+impl RouteParams {
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self
+      .0
+      .iter()
+      .find(|(k, _)| k == key)
+      .map(|(_, v)| v.as_str())
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+  }
+
+  pub fn parse<T>(&self, key: &str) -> Result<T, ParamError>
+  where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+  {
+    let value = self
+      .get(key)
+      .ok_or_else(|| ParamError::Missing(key.to_owned()))?;
+
+    value.parse().map_err(|err: T::Err| ParamError::Invalid {
+      key: key.to_owned(),
+      value: value.to_owned(),
+      error: err.to_string(),
+    })
+  }
+}
+
+// The params are matched against the percent-encoded path.
+impl<'k, 'v> From<&matchit::Params<'k, 'v>> for RouteParams {
+  fn from(params: &matchit::Params<'k, 'v>) -> Self {
+    Self(
+      params
+        .iter()
+        .map(|(k, v)| {
+          (
+            k.to_owned(),
+            percent_encoding::percent_decode_str(v)
+              .decode_utf8_lossy()
+              .into_owned(),
+          )
+        })
+        .collect(),
+    )
+  }
+}
+
+// What can't appear as is in a path segment. A catch-all param spans several segments, so it keeps
+// its `/`.
+const PARAM: &percent_encoding::AsciiSet = &CATCH_ALL_PARAM.add(b'/');
+const CATCH_ALL_PARAM: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
+  .add(b' ')
+  .add(b'"')
+  .add(b'#')
+  .add(b'%')
+  .add(b'<')
+  .add(b'>')
+  .add(b'?')
+  .add(b'`')
+  .add(b'{')
+  .add(b'}');
+
+/// Percent-encodes a param value for a route path, used by `#[derive(Routable)]`.
+#[doc(hidden)]
+pub fn encode_route_param(value: &str, catch_all: bool) -> String {
+  let set = if catch_all { CATCH_ALL_PARAM } else { PARAM };
+  percent_encoding::utf8_percent_encode(value, set).to_string()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamError {
+  Missing(String),
+  Invalid {
+    key: String,
+    value: String,
+    error: String,
+  },
+}
+
+impl std::fmt::Display for ParamError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ParamError::Missing(key) => write!(f, "The route param `{}` is missing", key),
+      ParamError::Invalid { key, value, error } => write!(
+        f,
+        "The route param `{}` with value `{}` is invalid: {}",
+        key, value, error
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ParamError {}
+
+pub type RoutableRoutes = fn() -> Vec<(&'static str, Option<Component>)>;
+
+/// Typed routes, usually implemented with `#[derive(Routable)]`, so links and navigation are
+/// checked at compile time instead of going through raw paths.
+pub trait Routable {
+  /// The route pattern of every variant, with the element to render when `Routes` registers it.
+  fn routes() -> Vec<(&'static str, Option<Component>)>
+  where
+    Self: Sized;
+
+  fn to_path(&self) -> String;
+
+  /// Builds the route for the variant at `index` in `routes` from the matched params.
+  fn from_route(index: usize, params: &RouteParams) -> Option<Self>
+  where
+    Self: Sized;
+
+  fn from_path(path: &str) -> Option<Self>
+  where
+    Self: Sized,
+  {
+    let mut router = matchit::Router::new();
+
+    for (index, (route, _)) in Self::routes().into_iter().enumerate() {
+      if let Err(err) = router.insert(route, index) {
+        tracing::error!("{:?}", err);
+        return None;
+      }
+    }
+
+    let matched = router.at(path).ok()?;
+    Self::from_route(*matched.value, &RouteParams::from(&matched.params))
+  }
+}
+
+// What a `Route` renders once matched; the router stores the chain from the outermost route down
+// to the matched one.
+#[derive(Clone, Default)]
+struct RouteDefinition {
+  absolute_path: String,
+  element: Option<Component>,
+  error_element: Option<Component>,
+  guard: Option<RouteGuard>,
+  guard_fallback: Option<Component>,
+  guard_redirect: Option<String>,
+  loader: Option<RouteLoader>,
+  loading: Option<Component>,
+  redirect: Option<String>,
+}
+
+impl PartialEq for RouteDefinition {
+  fn eq(&self, other: &Self) -> bool {
+    self.absolute_path == other.absolute_path
+      && same_component(self.element, other.element)
+      && same_component(self.error_element, other.error_element)
+      && self.guard == other.guard
+      && same_component(self.guard_fallback, other.guard_fallback)
+      && self.guard_redirect == other.guard_redirect
+      && self.loader == other.loader
+      && same_component(self.loading, other.loading)
+      && self.redirect == other.redirect
+  }
+}
+
+fn same_component(a: Option<Component>, b: Option<Component>) -> bool {
+  a.map(|a| a as usize) == b.map(|b| b as usize)
+}
+
+pub type GuardFuture = Pin<Box<dyn Future<Output = bool>>>;
+
+/// An async check that decides whether a matched route can be rendered. When it resolves to `false`
+/// the route renders its `guard_redirect` or `guard_fallback` instead of the element.
+#[derive(Clone)]
+pub struct RouteGuard(Rc<dyn Fn(RouteParams, Location) -> GuardFuture>);
+
+impl RouteGuard {
+  pub fn new<F, R>(guard: F) -> Self
+  where
+    F: Fn(RouteParams, Location) -> R + 'static,
+    R: Future<Output = bool> + 'static,
+  {
+    Self(Rc::new(move |params, location| {
+      Box::pin(guard(params, location))
+    }))
+  }
+}
+
+impl PartialEq for RouteGuard {
+  fn eq(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.0, &other.0)
+  }
+}
+
+#[derive(Props, PartialEq)]
+struct RouteMatchProps {
+  depth: usize,
+  params: RouteParams,
+  routes: Vec<RouteDefinition>,
+}
+
+// The matched route chain and the depth of the route `Outlet` renders next.
+#[derive(Clone, PartialEq)]
+struct OutletContext {
+  depth: usize,
+  routes: Vec<RouteDefinition>,
+}
+
+// Renders the route at `depth` of the matched chain and keeps the params and the chain in shared
+// contexts, so the elements re-render when only those change, e.g. from `/posts/1` to `/posts/2`.
+#[allow(non_snake_case)]
+fn RouteMatch(cx: Scope<RouteMatchProps>) -> Element {
+  let RouteMatchProps {
+    depth,
+    params,
+    routes,
+  } = &cx.props;
+  let outlet = OutletContext {
+    depth: depth + 1,
+    routes: routes.clone(),
+  };
+  use_context_provider(&cx, || params.clone());
+  use_context_provider(&cx, || outlet.clone());
+  let params_context = use_context::<RouteParams>(&cx)?;
+  let outlet_context = use_context::<OutletContext>(&cx)?;
+  let location = use_context::<MatchedRoutes>(&cx).map(|matched| matched.read().location.clone());
+
+  if &*params_context.read() != params {
+    *params_context.write() = params.clone();
+  }
+
+  if *outlet_context.read() != outlet {
+    *outlet_context.write() = outlet;
+  }
+
+  let route = routes.get(*depth)?.clone();
+
+  // Provided on every render, so links in the element resolve against the route matched now.
+  cx.provide_context(RouteContext {
+    absolute_path: route.absolute_path.clone(),
+    location,
+    routes: routes[..=*depth].to_vec(),
+  });
+
+  if route.guard.is_some() {
+    cx.render(rsx!(GuardedRoute { route: route }))
+  } else {
+    cx.render(rsx!(RouteElement { route: route }))
+  }
+}
+
+#[derive(Props, PartialEq)]
+struct RouteElementProps {
+  route: RouteDefinition,
+}
+
+#[allow(non_snake_case)]
+fn RouteElement(cx: Scope<RouteElementProps>) -> Element {
+  let route = &cx.props.route;
+
+  match route.error_element {
+    Some(C) => cx.render(rsx!(ErrorBoundary {
+      fallback: C,
+      RouteContent { route: route.clone() }
+    })),
+    None => cx.render(rsx!(RouteContent {
+      route: route.clone()
+    })),
+  }
+}
+
+// The closest route with a `loading` element, which components below it report pending data to
+#[derive(Clone, Default)]
+struct PendingContext(Option<PendingRoute>);
+
+#[derive(Clone)]
+struct PendingRoute {
+  scope: ScopeId,
+  scopes: Rc<RefCell<Vec<ScopeId>>>,
+  update: Arc<dyn Fn(ScopeId)>,
+}
+
+// Renders the element along with the `loading` element while something below it is pending.
+#[allow(non_snake_case)]
+fn RouteContent(cx: Scope<RouteElementProps>) -> Element {
+  let route = &cx.props.route;
+  let parent = cx
+    .use_hook(|| cx.consume_context::<PendingContext>().unwrap_or_default())
+    .clone();
+  let scopes = cx.use_hook(|| Rc::new(RefCell::new(vec![]))).clone();
+  let location = use_location(&cx);
+  let cache = use_context::<LoaderCache>(&cx);
+
+  // Provided on every render, the matched route and its `loading` element can change.
+  cx.provide_context(match route.loading {
+    Some(_) => PendingContext(Some(PendingRoute {
+      scope: cx.scope_id(),
+      scopes: scopes.clone(),
+      update: cx.schedule_update_any(),
+    })),
+    None => parent,
+  });
+
+  let loaded = match (&route.loader, cache) {
+    (Some(_), Some(cache)) => cache
+      .read()
+      .data
+      .contains_key(&loader_key(&route.absolute_path, &location)),
+    _ => true,
+  };
+  if !loaded {
+    return route
+      .loading
+      .and_then(|Loading| cx.render(rsx!(Loading {})));
+  }
+
+  let loading = match route.loading {
+    Some(Loading) if !scopes.borrow().is_empty() => Some(rsx!(Loading {})),
+    _ => None,
+  };
+
+  match (&route.redirect, route.element) {
+    (Some(to), _) => cx.render(rsx!(Redirect { to: to.as_str() })),
+    (None, Some(C)) => cx.render(rsx!(C {} loading)),
+    (None, None) => cx.render(rsx!(Outlet {} loading)),
+  }
+}
+
+// Runs the route guard again whenever the params or the location change, and renders nothing
+// until it resolves.
+#[allow(non_snake_case)]
+fn GuardedRoute(cx: Scope<RouteElementProps>) -> Element {
+  let route = &cx.props.route;
+  let params = use_params(&cx);
+  let location = use_location(&cx);
+  let matched_routes = use_context::<MatchedRoutes>(&cx);
+  let guard = route.guard.clone()?;
+  let allowed = use_future(&cx, (params, location), |(params, location)| {
+    (guard.0)(params, location)
+  });
+  use_navigation_state(
+    &cx,
+    allowed
+      .value()
+      .is_none()
+      .then_some(NavigationState::Loading),
+    false,
+  );
+
+  // Shared with the named outlets, which render the slots of this route only once it's allowed.
+  if let (Some(allowed), Some(matched_routes)) = (allowed.value(), matched_routes) {
+    if matched_routes.read().guards.get(&route.absolute_path) != Some(allowed) {
+      matched_routes
+        .write()
+        .guards
+        .insert(route.absolute_path.clone(), *allowed);
+    }
+  }
+
+  let allowed = match allowed.value() {
+    Some(allowed) => *allowed,
+    None => {
+      return route
+        .loading
+        .and_then(|Loading| cx.render(rsx!(Loading {})))
+    }
+  };
+
+  if allowed {
+    return cx.render(rsx!(RouteElement {
+      route: route.clone()
+    }));
+  }
+
+  match (&route.guard_redirect, route.guard_fallback) {
+    (Some(to), _) => cx.render(rsx!(Redirect { to: to.as_str() })),
+    (None, Some(Fallback)) => cx.render(rsx!(Fallback {})),
+    (None, None) => None,
+  }
+}
+
+#[derive(Props)]
+pub struct OutletProps<'a> {
+  // Renders the element a matched route set for this slot with `Slot`, instead of the next nested
+  // route
+  name: Option<&'a str>,
+}
+
+#[allow(non_snake_case)]
+pub fn Outlet<'a>(cx: Scope<'a, OutletProps<'a>>) -> Element {
+  if let Some(name) = cx.props.name {
+    return cx.render(rsx!(NamedOutlet { name: name }));
+  }
+
+  let context = use_context::<OutletContext>(&cx)?;
+  let params = use_params(&cx);
+  let OutletContext { depth, routes } = context.read().clone();
+
+  if depth >= routes.len() {
+    return None;
+  }
+
+  cx.render(rsx!(RouteMatch {
+    depth: depth,
+    params: params,
+    routes: routes,
+  }))
+}
+
+// Looks the slot up from the deepest matched route to the outermost one, and renders it with the
+// params and the relative links of the route that set it. The slot shows only where the route
+// itself would: once the main match allowed the guards of the chain down to that route, and not
+// when a route of the chain redirects.
+#[allow(non_snake_case)]
+#[inline_props]
+fn NamedOutlet<'a>(cx: Scope<'a>, name: &'a str) -> Element {
+  let context = use_context::<RoutesContext>(&cx)?;
+  let matched_routes = use_context::<MatchedRoutes>(&cx)?;
+  let MatchedRoutes {
+    guards,
+    params,
+    routes,
+    ..
+  } = matched_routes.read().clone();
+  let (index, element) = routes.iter().enumerate().rev().find_map(|(index, route)| {
+    let element = context
+      .read()
+      .slots
+      .get(&route.absolute_path)?
+      .iter()
+      .find(|(slot, _)| slot == name)
+      .map(|(_, element)| *element)?;
+    Some((index, element))
+  })?;
+
+  if routes[..=index].iter().any(|route| {
+    route.redirect.is_some()
+      || (route.guard.is_some() && guards.get(&route.absolute_path) != Some(&true))
+  }) {
+    return None;
+  }
+
+  let mut chain = routes[..=index]
+    .iter()
+    .map(|route| RouteDefinition {
+      absolute_path: route.absolute_path.clone(),
+      ..Default::default()
+    })
+    .collect::<Vec<_>>();
+  chain[index].element = Some(element);
+
+  cx.render(rsx!(RouteMatch {
+    depth: 0,
+    params: params,
+    routes: chain,
+  }))
+}
+
+/// Sets the element a `Route` renders in the `Outlet` with the same `name`, e.g. a sidebar next to
+/// the main content. Declared as a child of the `Route`.
+#[allow(non_snake_case)]
+#[inline_props]
+pub fn Slot<'a>(cx: Scope<'a>, name: &'a str, element: Component) -> Element {
+  let context = use_context::<RoutesContext>(&cx)?;
+
+  cx.use_hook(|| match cx.consume_context::<RouteContext>() {
+    Some(route) if !route.absolute_path.is_empty() => {
+      tracing::trace!("Registering slot {} of {}", name, route.absolute_path);
+      context
+        .write()
+        .slots
+        .entry(route.absolute_path)
+        .or_default()
+        .push((name.to_string(), *element));
+    }
+    _ => tracing::error!("`Slot` can be used only as a child of `Route`"),
+  });
+
+  None
+}
+
+// The error reported to the closest `ErrorBoundary`, with the `RouterCore.navigation` it was
+// reported during
+#[derive(Clone, Default)]
+struct BoundaryContext {
+  error: Option<(String, usize)>,
+}
+
+#[derive(Props)]
+pub struct ErrorBoundaryProps<'a> {
+  children: Element<'a>,
+  fallback: Option<Component>,
+}
+
+/// Renders `fallback` (or the `ErrorElement` from the context) instead of its children once one of
+/// them reports an error with `use_error_handler`, until the next navigation.
+///
+/// Only the errors reported that way are caught: a panic while rendering is not.
+#[allow(non_snake_case)]
+pub fn ErrorBoundary<'a>(cx: Scope<'a, ErrorBoundaryProps<'a>>) -> Element<'a> {
+  let ErrorBoundaryProps { children, fallback } = &cx.props;
+  use_context_provider(&cx, BoundaryContext::default);
+  let context = use_context::<BoundaryContext>(&cx)?;
+  let navigation =
+    use_context::<RouterCore>(&cx).map_or(0, |router_core| router_core.read().navigation);
+  let error = context
+    .read()
+    .error
+    .clone()
+    .filter(|(_, reported_during)| *reported_during == navigation)
+    .map(|(error, _)| error);
+
+  match (error, fallback) {
+    (None, _) => cx.render(rsx!(children)),
+    (Some(_), Some(Fallback)) => cx.render(rsx!(Fallback {})),
+    (Some(error), None) => cx.render(rsx!(InternalError { error: error })),
+  }
+}
+
+// Collects what the server needs to know after rendering a page, besides its html.
+#[derive(Clone, Default)]
+pub struct ServerContext {
+  head: Rc<RefCell<HeadTags>>,
+  // The components waiting for data: route guards, loaders and `use_pending`
+  pending: Rc<RefCell<Vec<ScopeId>>>,
+  redirect: Rc<RefCell<Option<String>>>,
+}
+
+impl ServerContext {
+  /// Renders `vdom`, provided with this context, until its route guards, loaders and `use_pending`
+  /// components are done waiting, or until the page redirects. Routes match, redirect and render
+  /// their `Title` and `Meta` after the first render, so `redirect_to`, `inject_head` and the html
+  /// are only complete once this returns.
+  ///
+  /// It blocks the current thread while waiting, since a `VirtualDom` can't be held across the
+  /// `.await` of a `Send` future like a request handler. There is no timeout: a guard or loader
+  /// that never resolves keeps the request waiting.
+  pub fn render(&self, vdom: &mut VirtualDom) {
+    let _ = vdom.rebuild();
+    render_pending(vdom);
+
+    while !self.pending.borrow().is_empty() && self.redirect_to().is_none() {
+      block_on(vdom.wait_for_work());
+      render_pending(vdom);
+    }
+  }
+
+  pub fn redirect(&self, to: &str) {
+    *self.redirect.borrow_mut() = Some(to.to_owned());
+  }
+
+  pub fn redirect_to(&self) -> Option<String> {
+    self.redirect.borrow().clone()
+  }
+}
+
+// Renders the scopes marked dirty since the last render, and the ones these renders mark dirty.
+fn render_pending(vdom: &mut VirtualDom) {
+  loop {
+    vdom.process_all_messages();
+    if vdom.work_with_deadline(|| false).is_empty() {
+      break;
+    }
+  }
+}
+
+// Polls `future` on the current thread until it's ready, parking the thread in between.
+fn block_on<F: Future>(future: F) -> F::Output {
+  struct ThreadWaker(std::thread::Thread);
+
+  impl std::task::Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+      self.0.unpark();
+    }
+  }
+
+  let waker = std::task::Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+  let mut cx = std::task::Context::from_waker(&waker);
+  let mut future = Box::pin(future);
+
+  loop {
+    if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+      return output;
+    }
+    std::thread::park();
+  }
+}
+
+#[allow(non_snake_case)]
+#[inline_props]
+pub fn Redirect<'a>(cx: Scope<'a>, to: &'a str) -> Element {
+  let router_core = use_context::<RouterCore>(&cx);
+  let to = use_route_base(&cx).borrow().resolve(to);
+
+  cx.use_hook(|| {
+    tracing::trace!("Redirecting to {}", to);
+
+    // The browser follows the server redirect, it needs the base path.
+    if let Some(server_context) = cx.consume_context::<ServerContext>() {
+      server_context.redirect(
+        &router_core.map_or_else(|| to.clone(), |router_core| router_core.read().href(&to)),
+      );
+    }
+
+    match router_core {
+      Some(router_core) => router_core.write().replace(&to),
+      None => tracing::error!("`Redirect` can be used only in components wrapped by a router"),
+    }
+  });
+
+  None
+}
+
+#[derive(Clone, Default)]
+pub struct RouteContext {
+  absolute_path: String,
+  // The location the enclosing `Routes` matched, the background one under a modal
+  location: Option<Location>,
+  routes: Vec<RouteDefinition>,
+}
+
+#[allow(non_snake_case)]
+#[inline_props]
+pub fn Route<'a>(
+  cx: Scope<'a>,
+  path: &'a str,
+  children: Element<'a>,
+  element: Option<Component>,
+  error_element: Option<Component>,
+  guard: Option<RouteGuard>,
+  guard_fallback: Option<Component>,
+  guard_redirect: Option<&'a str>,
+  loader: Option<RouteLoader>,
+  loading: Option<Component>,
+  redirect: Option<&'a str>,
+) -> Element<'a> {
+  let context = use_context::<RoutesContext>(&cx)?;
+  let error = use_state(&cx, || None);
+
+  cx.use_hook(|| {
+    tracing::trace!("Registering route: {}", path);
+    let (absolute_path, mut routes) = match cx.consume_context::<RouteContext>() {
+      Some(parent_context) if !parent_context.absolute_path.is_empty() => (
+        format!(
+          "{}/{}",
+          parent_context.absolute_path.trim_end_matches("/"),
+          path
+        ),
+        parent_context.routes,
+      ),
+      _ => (path.to_string(), vec![]),
+    };
+    routes.push(RouteDefinition {
+      absolute_path: absolute_path.clone(),
+      element: *element,
+      error_element: *error_element,
+      guard: guard.clone(),
+      guard_fallback: *guard_fallback,
+      guard_redirect: guard_redirect.map(|to| to.to_owned()),
+      loader: loader.clone(),
+      loading: *loading,
+      redirect: redirect.map(|to| to.to_owned()),
+    });
+
+    let route_context = cx.provide_context(RouteContext {
+      absolute_path,
+      location: None,
+      routes,
+    });
+
+    if let Err(err) = context
+      .write()
+      .router
+      .insert(route_context.absolute_path, route_context.routes)
+    {
+      tracing::error!("{:?}", err);
+      error.set(Some(err.to_string()));
+    }
+  });
+
+  cx.render(rsx!(
+    children
+    error.get().as_ref().map(|e| rsx!(InternalError { error: e.clone() }))
+  ))
+}
+
+// The route relative paths are resolved against: the pattern of the enclosing route, with the
+// params it matched.
+#[derive(Default)]
+struct RouteBase {
+  absolute_path: String,
+  params: RouteParams,
+}
+
+impl RouteBase {
+  fn resolve(&self, to: &str) -> String {
+    if to.starts_with('/') || is_external_url(to) {
+      return to.to_owned();
+    }
+
+    let (to, suffix) = match to.find(&['?', '#'][..]) {
+      Some(index) => to.split_at(index),
+      None => (to, ""),
+    };
+    let mut segments = self
+      .absolute_path
+      .split('/')
+      .map(|segment| match segment.chars().next() {
+        Some(':') => encode_route_param(self.params.get(&segment[1..]).unwrap_or_default(), false),
+        Some('*') => encode_route_param(self.params.get(&segment[1..]).unwrap_or_default(), true),
+        _ => segment.to_owned(),
+      })
+      .filter(|segment| !segment.is_empty())
+      .collect::<Vec<_>>();
+
+    for segment in to.split('/') {
+      match segment {
+        "" | "." => {}
+        ".." => {
+          let _ = segments.pop();
+        }
+        segment => segments.push(segment.to_owned()),
+      }
+    }
+
+    format!("/{}{}", segments.join("/"), suffix)
+  }
+}
+
+// Kept in a hook and refreshed on every render, so the closures returned by `use_navigate` can
+// borrow it and still be `Copy`.
+fn use_route_base(cx: &ScopeState) -> &RefCell<RouteBase> {
+  let params = use_context::<RouteParams>(&cx)
+    .map(|params| params.read().clone())
+    .unwrap_or_default();
+  let absolute_path = cx
+    .consume_context::<RouteContext>()
+    .map(|route_context| route_context.absolute_path)
+    .unwrap_or_default();
+  let route_base = cx.use_hook(|| RefCell::new(RouteBase::default()));
+  *route_base.borrow_mut() = RouteBase {
+    absolute_path,
+    params,
+  };
+  route_base
+}
+
+/// The current location. In the elements of a `Routes`, the location it matched: while a modal is
+/// open, the page under it keeps reading its background location.
+pub fn use_location(cx: &ScopeState) -> Location {
+  let context = use_context::<RouterCore>(&cx)
+    .expect("`use_location` can be used only in components wrapped by a router");
+
+  match cx
+    .consume_context::<RouteContext>()
+    .and_then(|route| route.location)
+  {
+    Some(location) => location,
+    None => context.read().location.clone().unwrap_or_default(),
+  }
+}
+
+pub fn use_search_params<T>(
+  cx: &ScopeState,
+) -> (
+  Result<T, serde_urlencoded::de::Error>,
+  SetSearchParams<'_, T>,
+)
+where
+  T: DeserializeOwned + Serialize,
+{
+  let context = use_context::<RouterCore>(&cx)
+    .expect("`use_search_params` can be used only in components wrapped by a router");
+  let location = use_location(cx);
+  let value = serde_urlencoded::from_str(location.search.trim_start_matches('?'));
+  let read = cx.use_hook(|| RefCell::new(Location::default()));
+  *read.borrow_mut() = location;
+
+  (
+    value,
+    SetSearchParams {
+      context,
+      location: read,
+      _marker: PhantomData,
+    },
+  )
+}
+
+pub struct SetSearchParams<'a, T> {
+  context: &'a UseSharedState<RouterCore>,
+  // The location the params were read from, the background one in the blocks rendering it
+  location: &'a RefCell<Location>,
+  _marker: PhantomData<fn(&T)>,
+}
+
+impl<'a, T> Clone for SetSearchParams<'a, T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<'a, T> Copy for SetSearchParams<'a, T> {}
+
+impl<'a, T> SetSearchParams<'a, T>
+where
+  T: Serialize,
+{
+  pub fn push(&self, value: &T) {
+    self.navigate(value, false);
+  }
+
+  pub fn replace(&self, value: &T) {
+    self.navigate(value, true);
+  }
+
+  // Updates the location the params were read from. Under a modal, that's either the modal
+  // location, which keeps its background, or the background, which keeps the modal open.
+  fn navigate(&self, value: &T, replace: bool) {
+    let search = match serde_urlencoded::to_string(value) {
+      Ok(search) => search,
+      Err(err) => {
+        tracing::error!("{:?}", &err);
+        return;
+      }
+    };
+    let read = self.location.borrow().clone();
+    let mut location = read.clone();
+    location.search = if search.is_empty() {
+      "".to_owned()
+    } else {
+      format!("?{}", search)
+    };
+
+    let current = self.context.read().location.clone().unwrap_or_default();
+    let (url, state, background) = match &current.background {
+      Some(background) if **background == read => {
+        (current.to_string(), current.state.clone(), Some(location))
+      }
+      _ => (
+        location.to_string(),
+        None,
+        location.background.map(|background| *background),
+      ),
+    };
+    let mut options = NavigateOptions {
+      replace,
+      scroll: false,
+      state,
+      background: None,
+    };
+    if let Some(background) = &background {
+      options = options.background(background);
+    }
+    self.context.write().navigate(&url, &options);
+  }
+}
+
+pub fn use_params(cx: &ScopeState) -> RouteParams {
+  match use_context::<RouteParams>(&cx) {
+    Some(context) => context.read().clone(),
+    None => {
+      tracing::error!("`use_params` can be used only in elements rendered by `Route`");
+      RouteParams::default()
+    }
+  }
+}
+
+pub fn use_param<T>(cx: &ScopeState, key: &str) -> Result<T, ParamError>
+where
+  T: std::str::FromStr,
+  T::Err: std::fmt::Display,
+{
+  use_params(cx).parse(key)
+}
+
+pub fn use_navigate(cx: &ScopeState) -> impl FnOnce(&str, NavigateOptions) + '_ + Copy {
+  let context = use_context::<RouterCore>(&cx)
+    .expect("`use_navigate` can be used only in components wrapped by a router");
+  let route_base = use_route_base(cx);
+  move |path, options| {
+    let path = route_base.borrow().resolve(path);
+    context.write().navigate(&path, &options)
+  }
+}
+
+pub fn use_navigate_route<R>(cx: &ScopeState) -> impl FnOnce(R, NavigateOptions) + '_ + Copy
+where
+  R: Routable,
+{
+  let navigate = use_navigate(cx);
+  move |route, options| navigate(&route.to_path(), options)
+}
+
+/// Reports an error to the closest `ErrorBoundary`, which renders its fallback instead. This is
+/// the only way errors reach a boundary.
+pub fn use_error_handler(cx: &ScopeState) -> impl Fn(&dyn std::fmt::Display) + Copy + '_ {
+  let context = use_context::<BoundaryContext>(&cx);
+  let router_core = use_context::<RouterCore>(&cx);
+
+  move |error| {
+    let error = error.to_string();
+    tracing::error!("{}", &error);
+
+    match context {
+      Some(context) => {
+        let navigation = router_core.map_or(0, |router_core| router_core.read().navigation);
+        context.write().error = Some((error, navigation));
+      }
+      None => {
+        tracing::error!(
+          "`use_error_handler` can be used only in components wrapped by an `ErrorBoundary`"
+        )
+      }
+    }
+  }
+}
+
+/// The error the closest `ErrorBoundary` caught, for its fallback to display. `None` once the
+/// router navigated since, like the boundary itself.
+pub fn use_route_error(cx: &ScopeState) -> Option<String> {
+  let context = use_context::<BoundaryContext>(&cx);
+  let navigation =
+    use_context::<RouterCore>(&cx).map_or(0, |router_core| router_core.read().navigation);
+  let error = context?.read().error.clone();
+  error
+    .filter(|(_, reported_during)| *reported_during == navigation)
+    .map(|(error, _)| error)
+}
+
+/// Marks the calling component as waiting for data while `pending` holds: the closest route with a
+/// `loading` element renders it, and `use_navigation` reports `Loading`.
+///
+/// ```ignore
+/// let todos = use_future(&cx, (), |_| fetch_todos());
+/// use_pending(&cx, todos.value().is_none());
+/// let todos = todos.value()?;
+/// ```
+pub fn use_pending(cx: &ScopeState, pending: bool) {
+  use_navigation_state(cx, pending.then_some(NavigationState::Loading), true);
+}
+
+/// Makes `use_navigation` report `Submitting` while `submitting` holds, e.g. while a form posts.
+pub fn use_submitting(cx: &ScopeState, submitting: bool) {
+  use_navigation_state(cx, submitting.then_some(NavigationState::Submitting), false);
+}
+
+pub fn use_navigation(cx: &ScopeState) -> NavigationState {
+  let context = use_context::<RouterCore>(&cx)
+    .expect("`use_navigation` can be used only in components wrapped by a router");
+  context.read().navigation_state()
+}
+
+fn use_navigation_state(cx: &ScopeState, state: Option<NavigationState>, route: bool) {
+  let context = use_context::<RouterCore>(&cx).cloned();
+  let hook = cx.use_hook(|| PendingHook {
+    context,
+    route: if route {
+      cx.consume_context::<PendingContext>().unwrap_or_default()
+    } else {
+      PendingContext::default()
+    },
+    scope: cx.scope_id(),
+    server_context: cx.consume_context::<ServerContext>(),
+    state: None,
+  });
+
+  if hook.state != state {
+    hook.set(state);
+  }
+}
+
+// Keeps what a component reported in `RouterCore`, in the closest route with a `loading` element
+// and, on the server, in `ServerContext`, until it unmounts.
+struct PendingHook {
+  context: Option<UseSharedState<RouterCore>>,
+  route: PendingContext,
+  scope: ScopeId,
+  server_context: Option<ServerContext>,
+  state: Option<NavigationState>,
+}
+
+impl PendingHook {
+  fn set(&mut self, state: Option<NavigationState>) {
+    self.state = state;
+
+    if let Some(context) = &self.context {
+      let mut router_core = context.write();
+      router_core
+        .pending
+        .retain(|(scope, _)| *scope != self.scope);
+      if let Some(state) = state {
+        router_core.pending.push((self.scope, state));
+      }
+    }
+
+    if let Some(server_context) = &self.server_context {
+      let mut pending = server_context.pending.borrow_mut();
+      pending.retain(|scope| *scope != self.scope);
+      if state == Some(NavigationState::Loading) {
+        pending.push(self.scope);
+      }
+    }
+
+    if let Some(route) = &self.route.0 {
+      {
+        let mut scopes = route.scopes.borrow_mut();
+        scopes.retain(|scope| *scope != self.scope);
+        if state == Some(NavigationState::Loading) {
+          scopes.push(self.scope);
+        }
+      }
+      (route.update)(route.scope);
+    }
+  }
+}
+
+impl Drop for PendingHook {
+  fn drop(&mut self) {
+    if self.state.is_some() {
+      self.set(None);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pathname(router_core: &RouterCore) -> &str {
+    router_core
+      .location
+      .as_ref()
+      .map_or("", |location| location.pathname.as_str())
+  }
+
+  #[test]
+  fn memory_router_starts_at_the_initial_index() {
+    assert_eq!(pathname(&RouterCore::memory(&[], None)), "/");
+    assert_eq!(pathname(&RouterCore::memory(&["/a", "/b"], None)), "/b");
+    assert_eq!(pathname(&RouterCore::memory(&["/a", "/b"], Some(0))), "/a");
+    assert_eq!(pathname(&RouterCore::memory(&["/a", "/b"], Some(5))), "/b");
+  }
+
+  #[test]
+  fn memory_router_history_stack() {
+    let mut router_core = RouterCore::memory(&["/", "/a"], None);
+    assert!(router_core.can_go_back());
+    assert!(!router_core.can_go_forward());
+
+    router_core.push("/b");
+    router_core.back();
+    router_core.back();
+    assert_eq!(pathname(&router_core), "/");
+    assert!(!router_core.can_go_back());
+
+    router_core.back();
+    assert_eq!(pathname(&router_core), "/");
+
+    router_core.forward();
+    assert_eq!(pathname(&router_core), "/a");
+    assert!(router_core.can_go_forward());
+
+    // Pushing drops the entries after the current one.
+    router_core.push("/c");
+    assert!(!router_core.can_go_forward());
+    assert_eq!(router_core.entries.len(), 3);
+
+    router_core.replace("/d");
+    assert_eq!(router_core.entries.len(), 3);
+    assert_eq!(pathname(&router_core), "/d");
+
+    router_core.back();
+    assert_eq!(pathname(&router_core), "/a");
+  }
+
+  #[test]
+  fn navigate_stores_the_state() {
+    let mut router_core = RouterCore::memory(&[], None);
+    router_core.navigate("/a", &NavigateOptions::default().state(&vec![1, 2]));
+    router_core.navigate("/b", &NavigateOptions::default());
+    router_core.navigate("/c", &NavigateOptions::default().replace(true));
+
+    assert_eq!(router_core.entries.len(), 3);
+    router_core.back();
+    let location = router_core.location.clone().unwrap();
+    assert_eq!(
+      location.parse_state::<Vec<u32>>().unwrap(),
+      Some(vec![1, 2])
+    );
+  }
+
+  #[test]
+  fn pop_state_ignores_the_current_entry() {
+    let mut router_core = RouterCore::memory(&["/", "/a"], None);
+    let navigation = router_core.navigation;
+
+    router_core.pop_state(Location::parse("/a"), Some(1));
+    assert_eq!(router_core.navigation, navigation);
+
+    router_core.pop_state(Location::parse("/"), Some(0));
+    assert_eq!(pathname(&router_core), "/");
+    assert_eq!(router_core.navigation, navigation + 1);
+  }
+
+  #[test]
+  fn locations_outside_the_base_path() {
+    let mut router_core =
+      RouterCore::memory(&["/other", "/app/posts"], None).with_base_path("/app");
+    assert_eq!(pathname(&router_core), "/posts");
+    assert!(!router_core.is_outside_base_path());
+
+    router_core.pop_state(Location::parse("/other"), Some(0));
+    assert_eq!(pathname(&router_core), "/other");
+    assert!(router_core.is_outside_base_path());
+
+    router_core.push("/posts/1");
+    assert!(!router_core.is_outside_base_path());
+  }
+
+  #[test]
+  fn pop_state_to_entries_pushed_before_a_reload() {
+    let mut router_core = RouterCore::memory(&["/a"], None);
+
+    router_core.pop_state(Location::parse("/c"), Some(2));
+    assert_eq!(pathname(&router_core), "/c");
+    assert_eq!(router_core.index, 2);
+    assert!(router_core.can_go_back());
+    assert!(!router_core.can_go_forward());
+  }
+
+  #[test]
+  fn parse_location() {
+    let location = Location::parse("/posts/1?page=2#comments");
+    assert_eq!(location.pathname, "/posts/1");
+    assert_eq!(location.search, "?page=2");
+    assert_eq!(location.hash, "#comments");
+    assert_eq!(location.to_string(), "/posts/1?page=2#comments");
+
+    let location = Location::parse("/posts?#");
+    assert_eq!(location.pathname, "/posts");
+    assert_eq!(location.search, "");
+    assert_eq!(location.hash, "");
+
+    // A `?` in the fragment is not a query string.
+    let location = Location::parse("/posts#a?b");
+    assert_eq!(location.pathname, "/posts");
+    assert_eq!(location.search, "");
+    assert_eq!(location.hash, "#a?b");
+  }
+
+  #[test]
+  fn resolve_relative_paths() {
+    let base = RouteBase {
+      absolute_path: "/users/:id/posts".to_owned(),
+      params: RouteParams(vec![("id".to_owned(), "7".to_owned())]),
+    };
+
+    assert_eq!(base.resolve("/about"), "/about");
+    assert_eq!(base.resolve("https://example.com"), "https://example.com");
+    assert_eq!(base.resolve("edit"), "/users/7/posts/edit");
+    assert_eq!(base.resolve("."), "/users/7/posts");
+    assert_eq!(base.resolve("../"), "/users/7");
+    assert_eq!(
+      base.resolve("../../settings?tab=1#top"),
+      "/users/settings?tab=1#top"
+    );
+    assert_eq!(RouteBase::default().resolve(".."), "/");
+  }
+
+  #[test]
+  fn active_paths() {
+    assert!(is_active_path("/posts", "/posts", false));
+    assert!(is_active_path("/posts/1", "/posts", false));
+    assert!(!is_active_path("/postsx", "/posts", false));
+    assert!(!is_active_path("/posts/1", "/posts", true));
+    assert!(is_active_path("/posts/", "/posts", true));
+    assert!(is_active_path("/posts", "/posts?page=2", true));
+    assert!(is_active_path("/", "/", false));
+    assert!(!is_active_path("/posts", "/", false));
+  }
+
+  #[test]
+  fn external_urls() {
+    assert!(is_external_url("https://example.com"));
+    assert!(is_external_url("mailto:hello@example.com"));
+    assert!(is_external_url("//cdn.example.com/app.js"));
+    assert!(!is_external_url("/posts"));
+    assert!(!is_external_url("edit"));
+    assert!(!is_external_url("./a:b"));
+  }
 }
@@ -0,0 +1,81 @@
+use std::{
+  any::Any,
+  collections::{HashMap, HashSet},
+  future::Future,
+  pin::Pin,
+  rc::Rc,
+};
+
+use dioxus::prelude::*;
+
+use crate::{use_location, Location, RouteContext, RouteParams};
+
+pub type LoaderFuture = Pin<Box<dyn Future<Output = Rc<dyn Any>>>>;
+
+/// Loads the data of a matched route before its element renders, read with `use_loader_data`. The
+/// data is cached by url and loaded again on every navigation, rendering the cached data meanwhile.
+#[derive(Clone)]
+pub struct RouteLoader(Rc<dyn Fn(RouteParams, Location) -> LoaderFuture>);
+
+impl RouteLoader {
+  pub fn new<F, R, T>(loader: F) -> Self
+  where
+    F: Fn(RouteParams, Location) -> R + 'static,
+    R: Future<Output = T> + 'static,
+    T: 'static,
+  {
+    Self(Rc::new(move |params, location| {
+      let future = loader(params, location);
+      Box::pin(async move { Rc::new(future.await) as Rc<dyn Any> })
+    }))
+  }
+}
+
+impl PartialEq for RouteLoader {
+  fn eq(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.0, &other.0)
+  }
+}
+
+// The data loaded by the route loaders, by route pattern and url
+#[derive(Clone, Default)]
+pub(crate) struct LoaderCache {
+  pub(crate) data: HashMap<(String, String), Rc<dyn Any>>,
+  pub(crate) loading: HashSet<(String, String)>,
+}
+
+// The hash is left out, it doesn't reach the server.
+pub(crate) fn loader_key(absolute_path: &str, location: &Location) -> (String, String) {
+  (
+    absolute_path.to_owned(),
+    format!("{}{}", location.pathname, location.search),
+  )
+}
+
+/// The data the `loader` of the closest route loaded for the current url, `None` while it's first
+/// loading or when it's not a `T`.
+pub fn use_loader_data<T>(cx: &ScopeState) -> Option<Rc<T>>
+where
+  T: 'static,
+{
+  let cache = use_context::<LoaderCache>(&cx)?;
+  let location = use_location(cx);
+  let route = cx.consume_context::<RouteContext>()?;
+  let data = cache
+    .read()
+    .data
+    .get(&loader_key(&route.absolute_path, &location))
+    .cloned()?;
+
+  match data.downcast::<T>() {
+    Ok(data) => Some(data),
+    Err(_) => {
+      tracing::error!(
+        "The loader of `{}` returns another type than `{}`",
+        route.absolute_path,
+        std::any::type_name::<T>()
+      );
+      None
+    }
+  }
+}
@@ -0,0 +1,100 @@
+#[cfg(target_arch = "wasm32")]
+use std::{
+  cell::{Cell, RefCell},
+  collections::HashMap,
+  rc::Rc,
+};
+
+#[cfg(target_arch = "wasm32")]
+use dioxus::prelude::*;
+
+#[cfg(target_arch = "wasm32")]
+use crate::RouterCore;
+
+// Where the window should scroll to once the current location has been rendered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Scroll {
+  Keep,
+  Restore,
+  Top,
+}
+
+// Records the scroll position of the current history entry as the window scrolls, and applies the
+// scroll a navigation asks for once its location has been rendered.
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct ScrollRestoration {
+  navigation: Cell<Option<usize>>,
+  positions: Rc<RefCell<HashMap<usize, (f64, f64)>>>,
+  _listener: gloo::events::EventListener,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl ScrollRestoration {
+  pub(crate) fn new(context: UseSharedState<RouterCore>) -> Self {
+    let window = web_sys::window().unwrap();
+
+    // The browser would restore the position before the previous entry is rendered again.
+    if let Err(err) = window
+      .history()
+      .and_then(|history| history.set_scroll_restoration(web_sys::ScrollRestoration::Manual))
+    {
+      tracing::error!("{:?}", &err);
+    }
+
+    let positions = Rc::new(RefCell::new(HashMap::new()));
+    let listener = gloo::events::EventListener::new(&window, "scroll", {
+      let positions = positions.clone();
+      move |_| {
+        let window = web_sys::window().unwrap();
+        positions.borrow_mut().insert(
+          context.read().index,
+          (
+            window.scroll_x().unwrap_or_default(),
+            window.scroll_y().unwrap_or_default(),
+          ),
+        );
+      }
+    });
+
+    Self {
+      navigation: Default::default(),
+      positions,
+      _listener: listener,
+    }
+  }
+
+  pub(crate) fn update(&self, router_core: &RouterCore) {
+    if self.navigation.replace(Some(router_core.navigation)) == Some(router_core.navigation) {
+      return;
+    }
+
+    let position = match router_core.scroll {
+      Scroll::Keep => return,
+      Scroll::Restore => self.positions.borrow().get(&router_core.index).copied(),
+      Scroll::Top => None,
+    };
+    let hash = router_core
+      .location
+      .as_ref()
+      .map(|location| location.hash.trim_start_matches('#').to_owned())
+      .unwrap_or_default();
+
+    // Rendering the new location is only applied to the DOM after this render returns.
+    gloo::timers::callback::Timeout::new(0, move || {
+      let window = web_sys::window().unwrap();
+      let element = match hash.as_str() {
+        "" => None,
+        id => window
+          .document()
+          .and_then(|document| document.get_element_by_id(id)),
+      };
+
+      match (position, element) {
+        (Some((x, y)), _) => window.scroll_to_with_x_and_y(x, y),
+        (None, Some(element)) => element.scroll_into_view(),
+        (None, None) => window.scroll_to_with_x_and_y(0.0, 0.0),
+      }
+    })
+    .forget();
+  }
+}
@@ -0,0 +1,343 @@
+use ahecha::*;
+use dioxus::prelude::*;
+
+fn render(app: Component) -> (String, ServerContext) {
+  let mut vdom = VirtualDom::new(app);
+  let server_context = vdom.base_scope().provide_context(ServerContext::default());
+  server_context.render(&mut vdom);
+  (dioxus::ssr::render_vdom(&vdom), server_context)
+}
+
+#[allow(non_snake_case)]
+fn Home(cx: Scope) -> Element {
+  cx.render(rsx!(
+    Title { title: "Home" }
+    Meta { name: "description", content: "The home page" }
+    h1 { "Home" }
+  ))
+}
+
+#[allow(non_snake_case)]
+fn About(cx: Scope) -> Element {
+  cx.render(rsx!(h1 { "About" }))
+}
+
+#[test]
+fn memory_router_renders_the_current_entry() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      initial_entries: &["/", "/about"],
+      initial_index: 0,
+      Routes {
+        Route { path: "/", element: Home }
+        Route { path: "/about", element: About }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<h1>Home</h1>"));
+  assert!(!html.contains("<h1>About</h1>"));
+}
+
+#[test]
+fn route_redirects_reach_the_server() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(BrowserRouter {
+      location: "/old",
+      Routes {
+        Route { path: "/", element: Home }
+        Route { path: "/old", redirect: "/" }
+      }
+    }))
+  }
+
+  let (_, server_context) = render(app);
+  assert_eq!(server_context.redirect_to().as_deref(), Some("/"));
+}
+
+#[test]
+fn guard_redirects_reach_the_server() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(BrowserRouter {
+      location: "/admin",
+      Routes {
+        Route { path: "/", element: Home }
+        Route {
+          path: "/admin",
+          element: Home,
+          guard: RouteGuard::new(|_, _| async { false }),
+          guard_redirect: "/",
+        }
+      }
+    }))
+  }
+
+  let (_, server_context) = render(app);
+  assert_eq!(server_context.redirect_to().as_deref(), Some("/"));
+}
+
+#[test]
+fn routed_head_tags_reach_the_server() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(BrowserRouter {
+      location: "/",
+      Routes {
+        Route { path: "/", element: Home }
+      }
+    }))
+  }
+
+  let (html, server_context) = render(app);
+  assert!(html.contains("<h1>Home</h1>"));
+  assert_eq!(
+    server_context.inject_head("<head><title>App</title></head>"),
+    r#"<head><title>Home</title><meta name="description" content="The home page"></head>"#
+  );
+}
+
+#[allow(non_snake_case)]
+fn Posts(cx: Scope) -> Element {
+  cx.render(rsx!(
+    h1 { "Posts" }
+    Outlet {}
+  ))
+}
+
+#[allow(non_snake_case)]
+fn Post(cx: Scope) -> Element {
+  let id = use_param::<u32>(&cx, "id").ok()?;
+  cx.render(rsx!(p { "Post #{id}" }))
+}
+
+#[test]
+fn outlets_render_the_nested_route() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      initial_entries: &["/posts/1"],
+      Routes {
+        Route {
+          path: "/posts",
+          element: Posts,
+          Route { path: ":id", element: Post }
+        }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<h1>Posts</h1>"));
+  assert!(html.contains("<p>Post #1</p>"));
+}
+
+#[allow(non_snake_case)]
+fn Forbidden(cx: Scope) -> Element {
+  cx.render(rsx!(h1 { "Forbidden" }))
+}
+
+#[test]
+fn guards_render_the_element_or_the_fallback() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      initial_entries: &["/posts/1"],
+      Routes {
+        Route {
+          path: "/posts",
+          element: Posts,
+          guard: RouteGuard::new(|_, _| async { true }),
+          Route {
+            path: ":id",
+            element: Post,
+            guard: RouteGuard::new(|_, _| async { false }),
+            guard_fallback: Forbidden,
+          }
+        }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<h1>Posts</h1>"));
+  assert!(html.contains("<h1>Forbidden</h1>"));
+  assert!(!html.contains("Post #1"));
+}
+
+#[allow(non_snake_case)]
+fn Broken(cx: Scope) -> Element {
+  let handle_error = use_error_handler(&cx);
+  cx.use_hook(|| handle_error(&"Unable to load the page"));
+  cx.render(rsx!(h1 { "Broken" }))
+}
+
+#[allow(non_snake_case)]
+fn ErrorPage(cx: Scope) -> Element {
+  let error = use_route_error(&cx)?;
+  cx.render(rsx!(p { "{error}" }))
+}
+
+#[test]
+fn error_boundaries_render_the_fallback() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      ErrorBoundary {
+        fallback: ErrorPage,
+        Routes {
+          Route { path: "/", element: Broken }
+        }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<p>Unable to load the page</p>"));
+  assert!(!html.contains("<h1>Broken</h1>"));
+}
+
+#[allow(non_snake_case)]
+fn Todos(cx: Scope) -> Element {
+  let todos = use_loader_data::<Vec<&str>>(&cx)?;
+  cx.render(rsx!(ul {
+    todos.iter().map(|todo| rsx!(li { "{todo}" }))
+  }))
+}
+
+#[test]
+fn loaders_provide_the_route_data() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      Routes {
+        Route {
+          path: "/",
+          element: Todos,
+          loader: RouteLoader::new(|_, _| async { vec!["Write the tests"] }),
+        }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<li>Write the tests</li>"));
+}
+
+#[allow(non_snake_case)]
+#[inline_props]
+fn Layout<'a>(cx: Scope<'a>, children: Element<'a>) -> Element<'a> {
+  cx.render(rsx!(
+    aside { Outlet { name: "promo" } }
+    main { children }
+  ))
+}
+
+#[allow(non_snake_case)]
+fn Promo(cx: Scope) -> Element {
+  cx.render(rsx!(p { "Promo" }))
+}
+
+#[test]
+fn slots_render_in_named_outlets() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      initial_entries: &["/posts/1"],
+      Routes {
+        Layout {
+          Route {
+            path: "/posts",
+            element: Posts,
+            Slot { name: "promo", element: Promo }
+            Route { path: ":id", element: Post }
+          }
+        }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<p>Promo</p>"));
+  assert!(html.contains("<p>Post #1</p>"));
+}
+
+#[allow(non_snake_case)]
+fn PostModal(cx: Scope) -> Element {
+  let id = use_param::<u32>(&cx, "id").ok()?;
+  cx.render(rsx!(dialog { "Modal #{id}" }))
+}
+
+#[test]
+fn modal_routes_render_over_the_background() {
+  fn app(cx: Scope) -> Element {
+    use_context_provider(&cx, || {
+      let mut router_core = RouterCore::memory(&["/posts"], None);
+      router_core.navigate(
+        "/posts/2",
+        &NavigateOptions::default().background(&Location::parse("/posts")),
+      );
+      router_core
+    });
+
+    cx.render(rsx!(
+      Routes {
+        Route {
+          path: "/posts",
+          element: Posts,
+          Route { path: ":id", element: Post }
+        }
+      }
+      Routes {
+        modal: true,
+        Route { path: "/posts/:id", element: PostModal }
+      }
+    ))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<h1>Posts</h1>"));
+  assert!(!html.contains("Post #2"));
+  assert!(html.contains("Modal #2"));
+}
+
+#[test]
+fn slots_wait_for_the_route_guards() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(MemoryRouter {
+      initial_entries: &["/posts"],
+      Routes {
+        Layout {
+          Route {
+            path: "/posts",
+            element: Posts,
+            guard: RouteGuard::new(|_, _| async { false }),
+            guard_fallback: Forbidden,
+            Slot { name: "promo", element: Promo }
+          }
+        }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<h1>Forbidden</h1>"));
+  assert!(!html.contains("<p>Promo</p>"));
+}
+
+#[allow(non_snake_case)]
+fn NotFound(cx: Scope) -> Element {
+  cx.render(rsx!(h1 { "Not Found" }))
+}
+
+#[test]
+fn locations_outside_the_base_path_render_the_fallback() {
+  fn app(cx: Scope) -> Element {
+    cx.render(rsx!(BrowserRouter {
+      base_path: "/app",
+      location: "/other",
+      Routes {
+        Route { path: "/other", element: Home }
+        Fallback { element: NotFound }
+      }
+    }))
+  }
+
+  let (html, _) = render(app);
+  assert!(html.contains("<h1>Not Found</h1>"));
+  assert!(!html.contains("<h1>Home</h1>"));
+}
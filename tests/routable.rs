@@ -0,0 +1,67 @@
+use ahecha::Routable;
+
+#[derive(Debug, PartialEq, Routable)]
+enum AppRoute {
+  #[at("/")]
+  Home,
+  #[at("/posts/:id")]
+  Post { id: u32 },
+  #[at("/users/:name/posts/:id")]
+  UserPost { id: u32, name: String },
+  #[at("/files/*path")]
+  File { path: String },
+}
+
+#[test]
+fn routable_round_trip() {
+  let routes = [
+    AppRoute::Home,
+    AppRoute::Post { id: 1 },
+    AppRoute::UserPost {
+      id: 2,
+      name: "ana".to_owned(),
+    },
+    AppRoute::UserPost {
+      id: 3,
+      name: "ana/maría? #1 100%".to_owned(),
+    },
+    AppRoute::File {
+      path: "docs/read me.md".to_owned(),
+    },
+  ];
+
+  for route in routes {
+    assert_eq!(AppRoute::from_path(&route.to_path()), Some(route));
+  }
+}
+
+#[test]
+fn routable_paths() {
+  assert_eq!(AppRoute::Home.to_path(), "/");
+  assert_eq!(AppRoute::Post { id: 1 }.to_path(), "/posts/1");
+  assert_eq!(
+    AppRoute::UserPost {
+      id: 2,
+      name: "ana".to_owned()
+    }
+    .to_path(),
+    "/users/ana/posts/2"
+  );
+  assert_eq!(
+    AppRoute::UserPost {
+      id: 3,
+      name: "a/b c".to_owned()
+    }
+    .to_path(),
+    "/users/a%2Fb%20c/posts/3"
+  );
+  assert_eq!(
+    AppRoute::File {
+      path: "docs/read me.md".to_owned()
+    }
+    .to_path(),
+    "/files/docs/read%20me.md"
+  );
+  assert_eq!(AppRoute::from_path("/posts/x"), None);
+  assert_eq!(AppRoute::from_path("/unknown"), None);
+}